@@ -9,7 +9,7 @@
  */
 
 
-use reqwest::{self, StatusCode};
+use reqwest;
 use serde::{Deserialize, Serialize};
 use crate::{apis::ResponseContent, models};
 use super::{Error, configuration};
@@ -278,7 +278,7 @@ pub async fn get_system_waypoints(configuration: &configuration::Configuration,
 }
 
 /// Return a paginated list of all systems.
-pub async fn get_systems_page(configuration: &configuration::Configuration, page: Option<i32>, limit: Option<i32>) -> Result<models::GetSystems200Response, Error<GetSystemsError>> {
+pub async fn get_systems(configuration: &configuration::Configuration, page: Option<i32>, limit: Option<i32>) -> Result<models::GetSystems200Response, Error<GetSystemsError>> {
     let local_var_configuration = configuration;
 
     let local_var_client = &local_var_configuration.client;
@@ -314,45 +314,6 @@ pub async fn get_systems_page(configuration: &configuration::Configuration, page
     }
 }
 
-pub async fn get_systems(configuration: &configuration::Configuration) -> Result<Vec<models::System>, Error<GetSystemsError>> {
-    let first_page =
-        get_systems_page(configuration, Some(1), Some(20))
-            .await?;
-
-    let mut systems = first_page.data;
-    let mut remaining_systems = first_page.meta.total - systems.len() as i32;
-    let mut page = 2;
-
-    while remaining_systems > 0 {
-        let res = get_systems_page(configuration, Some(page), Some(20)).await;
-
-        match res {
-            Err(err) => {
-                match err {
-                    Error::ResponseError(response)
-                        if response.status == StatusCode::TOO_MANY_REQUESTS =>
-                    {
-                        println!("Retrying system page fetch...");
-                        continue;
-                    }
-                    _ => return Err(err)
-                }
-            },
-            Ok(mut response) => {
-                let new_systems_count = response.data.len() as i32;
-
-                systems.append(&mut response.data);
-                remaining_systems -= new_systems_count as i32;
-                page += 1;
-
-                println!("Pulled {} systems. Remaining systems: {}, Total systems: {}", new_systems_count, remaining_systems, systems.len())
-            }
-        }
-    }
-
-    Ok(systems)
-}
-
 /// View the details of a waypoint.  If the waypoint is uncharted, it will return the 'Uncharted' trait instead of its actual traits.
 pub async fn get_waypoint(configuration: &configuration::Configuration, system_symbol: &str, waypoint_symbol: &str) -> Result<models::GetWaypoint200Response, Error<GetWaypointError>> {
     let local_var_configuration = configuration;
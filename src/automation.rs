@@ -0,0 +1,155 @@
+pub mod construction;
+pub mod contract;
+pub mod crawler;
+pub mod daemon;
+pub mod events;
+pub mod explorer;
+pub mod maintenance;
+pub mod market;
+pub mod mining;
+pub mod probes;
+pub mod scanner;
+pub mod script;
+pub mod tasks;
+mod control;
+mod ship;
+
+use std::future::Future;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use reqwest::StatusCode;
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+
+use crate::time;
+
+/// The number of items per page, the maximum allowed by the API
+const PAGE_LIMIT: i32 = 20;
+
+/// The minimum delay between two requests, the API allows 2 requests per second
+const REQUEST_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Shared by every ship so they don't exceed the API rate limit together
+static RATE_LIMITER: LazyLock<RateLimiter> = LazyLock::new(|| RateLimiter::new(REQUEST_INTERVAL));
+
+struct RateLimiter {
+    interval: Duration,
+    /// The time at which the next request can be sent
+    next_request: Mutex<tokio::time::Instant>,
+}
+
+impl RateLimiter {
+    fn new(interval: Duration) -> RateLimiter {
+        RateLimiter {
+            interval,
+            next_request: Mutex::new(tokio::time::Instant::now()),
+        }
+    }
+
+    /// Wait for the turn of the request
+    async fn wait(&self) {
+        let request_time = {
+            let mut next_request = self.next_request.lock().unwrap();
+            let request_time = (*next_request).max(tokio::time::Instant::now());
+            *next_request = request_time + self.interval;
+            request_time
+        };
+
+        tokio::time::sleep_until(request_time).await;
+    }
+}
+
+/// Send a request once the rate limiter allows it,
+/// retrying it while the API rate limit is reached
+pub async fn retry<T, E, F, Fut>(mut request: F) -> Result<T, apis::Error<E>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, apis::Error<E>>>,
+{
+    loop {
+        RATE_LIMITER.wait().await;
+
+        match request().await {
+            Err(apis::Error::ResponseError(response))
+                if response.status == StatusCode::TOO_MANY_REQUESTS =>
+            {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+            res => return res,
+        }
+    }
+}
+
+/// Send a paginated request page by page through `retry`, collecting the items of every page
+async fn paginate<T, E, F, Fut>(mut request: F) -> Result<Vec<T>, apis::Error<E>>
+where
+    F: FnMut(i32) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, models::Meta), apis::Error<E>>>,
+{
+    let mut items = Vec::new();
+    let mut page = 1;
+
+    loop {
+        let (mut data, meta) = retry(|| request(page)).await?;
+        items.append(&mut data);
+
+        if meta.page * meta.limit >= meta.total {
+            return Ok(items)
+        }
+        page += 1;
+    }
+}
+
+/// Every ship of the agent
+pub async fn get_my_ships(config: &Configuration) -> Result<Vec<models::Ship>, apis::Error<apis::fleet_api::GetMyShipsError>> {
+    paginate(|page| async move {
+        apis::fleet_api::get_my_ships(config, Some(page), Some(PAGE_LIMIT))
+            .await
+            .map(|res| (res.data, *res.meta))
+    }).await
+}
+
+/// Every contract of the agent
+pub async fn get_contracts(config: &Configuration) -> Result<Vec<models::Contract>, apis::Error<apis::contracts_api::GetContractsError>> {
+    paginate(|page| async move {
+        apis::contracts_api::get_contracts(config, Some(page), Some(PAGE_LIMIT))
+            .await
+            .map(|res| (res.data, *res.meta))
+    }).await
+}
+
+/// The SpaceTraders error code of a failed request, like `4224` for an exhausted survey
+pub fn error_code<T>(err: &apis::Error<T>) -> Option<i64> {
+    let apis::Error::ResponseError(response) = err else {
+        return None
+    };
+
+    serde_json::from_str::<serde_json::Value>(&response.content)
+        .ok()?
+        .pointer("/error/code")?
+        .as_i64()
+}
+
+/// Sleep until the given API timestamp, if it's in the future
+pub async fn sleep_until(timestamp: &str) {
+    let Some(date) = time::parse(timestamp) else {
+        return
+    };
+
+    if let Ok(duration) = (date - Utc::now()).to_std() {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+pub fn log(ship_symbol: &str, message: impl std::fmt::Display) {
+    let entry = events::LogEntry {
+        timestamp: Utc::now(),
+        ship_symbol: ship_symbol.to_string(),
+        message: message.to_string(),
+    };
+
+    println!("[{}] [{ship_symbol}] {}", entry.timestamp.format("%H:%M:%S"), entry.message);
+    events::record_log(entry);
+}
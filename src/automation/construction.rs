@@ -0,0 +1,197 @@
+use std::collections::BTreeSet;
+
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+
+use super::market::fetch_market;
+use super::{log, retry, ship};
+use crate::cli::{flag_value, get_token};
+use crate::store::constructions::{self, missing_units, ConstructionStore};
+use crate::store::galaxy::GalaxyStore;
+use crate::store::ledger;
+use crate::store::markets::{self, MarketStore};
+use crate::symbols::system_symbol_of;
+
+/// Where to buy a construction material
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub market: String,
+    /// The last known purchase price at the market
+    pub price_per_unit: Option<i32>,
+    pub supply: Option<models::SupplyLevel>,
+}
+
+/// `space-trader construction --token=<token> [--waypoint=<symbol>] [--ship=<symbol>]`
+///
+/// Print the progress of the known construction sites with where to buy the missing materials,
+/// and supply a site with a ship until it's complete when `--ship` is given
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+
+    let sites = match flag_value(args, "--waypoint=") {
+        Some(waypoint_symbol) => vec![waypoint_symbol.to_string()],
+        None => known_sites()?,
+    };
+    if sites.is_empty() {
+        return Err(String::from("No known construction site, fetch the waypoints of a system first"))
+    }
+
+    let store = MarketStore::load()?;
+    for waypoint_symbol in &sites {
+        let construction = fetch_construction(&config, waypoint_symbol).await?;
+        let status = if construction.is_complete { "complete" } else { "under construction" };
+        println!("{waypoint_symbol} ({status})");
+
+        for material in &construction.materials {
+            let source = match (missing_units(material), find_source(&store, system_symbol_of(waypoint_symbol), material.trade_symbol)) {
+                (0, _) => String::from("supplied"),
+                (_, Some(source)) => describe_source(&source),
+                (_, None) => String::from("no known market sells it"),
+            };
+            println!("  {} {}/{}, {source}", material.trade_symbol, material.fulfilled, material.required);
+        }
+    }
+
+    let Some(ship_symbol) = flag_value(args, "--ship=") else {
+        return Ok(())
+    };
+    let [waypoint_symbol] = sites.as_slice() else {
+        return Err(String::from("The construction site to supply must be given with --waypoint"))
+    };
+    let ship = *retry(|| apis::fleet_api::get_my_ship(&config, ship_symbol))
+        .await
+        .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))?
+        .data;
+
+    run(&config, ship, waypoint_symbol).await
+}
+
+/// The construction sites seen in the fetched waypoints, and the unfinished ones already fetched
+pub fn known_sites() -> Result<Vec<String>, String> {
+    let mut sites: BTreeSet<String> = GalaxyStore::load()?
+        .under_construction()
+        .map(|waypoint| waypoint.symbol.clone())
+        .collect();
+    sites.extend(
+        ConstructionStore::load()?
+            .sites()
+            .into_iter()
+            .filter(|snapshot| !snapshot.construction.is_complete)
+            .map(|snapshot| snapshot.construction.symbol.clone())
+    );
+
+    Ok(sites.into_iter().collect())
+}
+
+/// Get a construction site and store it
+pub async fn fetch_construction(config: &Configuration, waypoint_symbol: &str) -> Result<models::Construction, String> {
+    let construction = *retry(|| apis::systems_api::get_construction(config, system_symbol_of(waypoint_symbol), waypoint_symbol))
+        .await
+        .map_err(|err| format!("Failed to get the construction site {waypoint_symbol}: {err}"))?
+        .data;
+
+    constructions::record(construction.clone())?;
+    Ok(construction)
+}
+
+/// Find the market of the system selling a material at the lowest known price.
+/// Markets we don't have prices for come last.
+pub fn find_source(store: &MarketStore, system_symbol: &str, good: models::TradeSymbol) -> Option<Source> {
+    store
+        .latest_in(system_symbol)
+        .filter(|snapshot| markets::sells(&snapshot.market, good))
+        .map(|snapshot| {
+            let trade_good = store.latest_trade_good(&snapshot.market.symbol, good);
+            Source {
+                market: snapshot.market.symbol.clone(),
+                price_per_unit: trade_good.map(|trade_good| trade_good.purchase_price),
+                supply: trade_good.map(|trade_good| trade_good.supply),
+            }
+        })
+        .min_by_key(|source| source.price_per_unit.unwrap_or(i32::MAX))
+}
+
+pub fn describe_source(source: &Source) -> String {
+    match (source.price_per_unit, source.supply) {
+        (Some(price), Some(supply)) => format!("buy at {} for {price} credits ({supply} supply)", source.market),
+        _ => format!("buy at {}, unknown price", source.market),
+    }
+}
+
+/// Haul the missing materials to a construction site of the ship's system until it's complete
+pub async fn run(config: &Configuration, mut ship: models::Ship, waypoint_symbol: &str) -> Result<(), String> {
+    ledger::set_strategy(&ship.symbol, "construction");
+    if system_symbol_of(waypoint_symbol) != ship.nav.system_symbol {
+        return Err(format!("The construction site {waypoint_symbol} is not in the ship's system {}", ship.nav.system_symbol))
+    }
+
+    let mut store = MarketStore::load()?;
+    ship::wait_for_arrival(&mut ship).await;
+    ship::wait_for_cooldown(&mut ship).await;
+
+    loop {
+        let construction = fetch_construction(config, waypoint_symbol).await?;
+        let missing = construction.materials.iter().find(|material| missing_units(material) > 0);
+        let (false, Some(material)) = (construction.is_complete, missing) else {
+            log(&ship.symbol, format!("The construction of {waypoint_symbol} is complete"));
+            return Ok(())
+        };
+
+        supply(config, &mut store, &mut ship, waypoint_symbol, material).await?;
+    }
+}
+
+/// Buy a cargo load of a material and supply it to the construction site
+async fn supply(
+    config: &Configuration,
+    store: &mut MarketStore,
+    ship: &mut models::Ship,
+    waypoint_symbol: &str,
+    material: &models::ConstructionMaterial,
+) -> Result<(), String> {
+    let good = material.trade_symbol;
+    let in_cargo = ship::units_in_cargo(ship, good);
+    let units = (missing_units(material) - in_cargo).min(ship.cargo.capacity - ship.cargo.units).max(0);
+    if units == 0 && in_cargo == 0 {
+        return Err(format!("{} has no cargo space left for {good}", ship.symbol))
+    }
+
+    if units > 0 {
+        let source = find_source(store, &ship.nav.system_symbol, good)
+            .ok_or(format!("No known market sells {good} in {}", ship.nav.system_symbol))?;
+        log(&ship.symbol, format!("Sourcing {units} {good}: {}", describe_source(&source)));
+
+        ship::navigate(config, ship, &source.market).await?;
+        ship::refuel(config, ship).await?;
+
+        // The trade volume is only known when the ship is at the market
+        let market = fetch_market(config, store, &source.market).await?;
+        let trade_volume = market.trade_goods
+            .iter()
+            .flatten()
+            .find(|trade_good| trade_good.symbol == good)
+            .map_or(units, |trade_good| trade_good.trade_volume);
+
+        ship::purchase(config, ship, good, units, trade_volume).await?;
+    }
+
+    ship::navigate(config, ship, waypoint_symbol).await?;
+    ship::dock(config, ship).await?;
+    ship::refuel(config, ship).await?;
+
+    let units = ship::units_in_cargo(ship, good).min(missing_units(material));
+    let request = models::SupplyConstructionRequest::new(ship.symbol.clone(), good.to_string(), units);
+    let response = retry(|| {
+        apis::systems_api::supply_construction(config, &ship.nav.system_symbol, waypoint_symbol, Some(request.clone()))
+    })
+        .await
+        .map_err(|err| format!("Failed to supply {units} {good} to {waypoint_symbol}: {err}"))?;
+
+    ship.cargo = response.data.cargo;
+    let construction = *response.data.construction;
+    if let Some(material) = construction.materials.iter().find(|material| material.trade_symbol == good) {
+        log(&ship.symbol, format!("Supplied {units} {good}, {}/{}", material.fulfilled, material.required));
+    }
+
+    constructions::record(construction)
+}
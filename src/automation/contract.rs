@@ -0,0 +1,221 @@
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models::{self, contract::Type as ContractType};
+
+use super::market::{distance, fetch_market, get_marketplaces};
+use super::{log, retry, ship};
+use crate::cli::{flag_value, get_token, has_flag};
+use crate::store::ledger::{self, LedgerEntry};
+use crate::store::markets::{self, MarketStore};
+use crate::symbols::{parse_trade_symbol, system_symbol_of};
+
+/// A round trip buying a good at a market and delivering it to the contract's destination
+#[derive(Debug)]
+pub struct Trip {
+    pub good: models::TradeSymbol,
+    pub market: String,
+    pub destination: String,
+    pub units: i32,
+    /// The last known purchase price at the market
+    pub price_per_unit: Option<i32>,
+}
+
+/// `space-trader run-contract --contract=<id> --ship=<symbol> --token=<token> [--dry-run]`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+    let contract_id = flag_value(args, "--contract=").ok_or("No contract argument")?;
+    let ship_symbol = flag_value(args, "--ship=").ok_or("No ship argument")?;
+
+    run(&config, contract_id, ship_symbol, has_flag(args, "--dry-run")).await
+}
+
+/// Run a procurement contract with a ship until it's fulfilled.
+/// In dry-run mode, the plan is only printed.
+pub async fn run(config: &Configuration, contract_id: &str, ship_symbol: &str, dry_run: bool) -> Result<(), String> {
+    ledger::set_strategy(ship_symbol, "contract");
+    let mut store = MarketStore::load()?;
+    let mut contract = *retry(|| apis::contracts_api::get_contract(config, contract_id))
+        .await
+        .map_err(|err| format!("Failed to get contract {contract_id}: {err}"))?
+        .data;
+    let mut ship = *retry(|| apis::fleet_api::get_my_ship(config, ship_symbol))
+        .await
+        .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))?
+        .data;
+
+    if contract.r#type != ContractType::Procurement {
+        return Err(format!("Contract {contract_id} is not a procurement contract"))
+    }
+    if contract.fulfilled {
+        log(ship_symbol, format!("Contract {contract_id} is already fulfilled"));
+        return Ok(())
+    }
+
+    let trips = plan(config, &mut store, &contract, &ship).await?;
+    for trip in &trips {
+        let price = trip.price_per_unit.map_or(String::from("unknown price"), |price| format!("~{} credits", price * trip.units));
+        log(ship_symbol, format!("Plan: buy {} {} at {} ({price}), deliver to {}", trip.units, trip.good, trip.market, trip.destination));
+    }
+    if dry_run {
+        return Ok(())
+    }
+
+    if !contract.accepted {
+        let response = retry(|| apis::contracts_api::accept_contract(config, contract_id))
+            .await
+            .map_err(|err| format!("Failed to accept contract {contract_id}: {err}"))?;
+
+        log(ship_symbol, format!("Accepted contract {contract_id}, {} credits", response.data.agent.credits));
+        ledger::record(LedgerEntry::from_contract(&response.data.contract, Some(ship_symbol), &response.data.agent));
+        contract = *response.data.contract;
+    }
+
+    ship::wait_for_arrival(&mut ship).await;
+    ship::wait_for_cooldown(&mut ship).await;
+
+    for trip in trips {
+        contract = run_trip(config, &mut store, &mut ship, &contract, &trip).await?;
+    }
+
+    let response = retry(|| apis::contracts_api::fulfill_contract(config, contract_id))
+        .await
+        .map_err(|err| format!("Failed to fulfill contract {contract_id}: {err}"))?;
+
+    log(ship_symbol, format!("Fulfilled contract {contract_id}, {} credits", response.data.agent.credits));
+    ledger::record(LedgerEntry::from_contract(&response.data.contract, Some(ship_symbol), &response.data.agent));
+    Ok(())
+}
+
+/// Plan the trips needed to deliver the remaining goods of the contract
+pub async fn plan(
+    config: &Configuration,
+    store: &mut MarketStore,
+    contract: &models::Contract,
+    ship: &models::Ship,
+) -> Result<Vec<Trip>, String> {
+    let mut trips = Vec::new();
+
+    for deliver in contract.terms.deliver.iter().flatten() {
+        let good = parse_trade_symbol(&deliver.trade_symbol)
+            .ok_or(format!("Unknown trade symbol {}", deliver.trade_symbol))?;
+
+        let (market, price_per_unit) = find_cheapest_market(config, store, good, &deliver.destination_symbol).await?;
+
+        // The units already in the cargo are delivered during the first trip
+        let mut in_cargo = ship::units_in_cargo(ship, good);
+        let mut remaining = deliver.units_required - deliver.units_fulfilled;
+        let free_space = ship.cargo.capacity - ship.cargo.units + in_cargo;
+        if free_space <= 0 {
+            return Err(format!("{} has no cargo space left for {good}", ship.symbol))
+        }
+
+        while remaining > 0 {
+            let carried = remaining.min(free_space);
+            trips.push(Trip {
+                good,
+                market: market.clone(),
+                destination: deliver.destination_symbol.clone(),
+                units: carried - in_cargo.min(carried),
+                price_per_unit,
+            });
+
+            remaining -= carried;
+            in_cargo = 0;
+        }
+    }
+
+    Ok(trips)
+}
+
+/// Find the market selling the good at the lowest known price in the destination's system.
+/// Markets we don't have prices for are ranked by their distance to the destination.
+async fn find_cheapest_market(
+    config: &Configuration,
+    store: &mut MarketStore,
+    good: models::TradeSymbol,
+    destination: &str,
+) -> Result<(String, Option<i32>), String> {
+    let marketplaces = get_marketplaces(config, system_symbol_of(destination)).await?;
+    let destination_position = marketplaces
+        .iter()
+        .find(|waypoint| waypoint.symbol == destination)
+        .map(|waypoint| (waypoint.x, waypoint.y));
+
+    let mut candidates = Vec::new();
+    for waypoint in &marketplaces {
+        let market = match store.latest(&waypoint.symbol) {
+            Some(snapshot) => snapshot.market.clone(),
+            None => fetch_market(config, store, &waypoint.symbol).await?,
+        };
+        if !markets::sells(&market, good) {
+            continue
+        }
+
+        let price = store.latest_trade_good(&waypoint.symbol, good).map(|trade_good| trade_good.purchase_price);
+        let distance = destination_position.map_or(0., |position| distance(position, (waypoint.x, waypoint.y)));
+        candidates.push((waypoint.symbol.clone(), price, distance));
+    }
+
+    candidates
+        .into_iter()
+        .min_by(|(_, price_a, distance_a), (_, price_b, distance_b)| {
+            let price_a = price_a.unwrap_or(i32::MAX);
+            let price_b = price_b.unwrap_or(i32::MAX);
+            price_a.cmp(&price_b).then(distance_a.total_cmp(distance_b))
+        })
+        .map(|(market, price, _)| (market, price))
+        .ok_or(format!("No market sells {good} in {}", system_symbol_of(destination)))
+}
+
+async fn run_trip(
+    config: &Configuration,
+    store: &mut MarketStore,
+    ship: &mut models::Ship,
+    contract: &models::Contract,
+    trip: &Trip,
+) -> Result<models::Contract, String> {
+    if trip.units > 0 {
+        ship::navigate(config, ship, &trip.market).await?;
+        ship::refuel(config, ship).await?;
+
+        // The trade volume is only known when the ship is at the market
+        let market = fetch_market(config, store, &trip.market).await?;
+        let trade_volume = market.trade_goods
+            .iter()
+            .flatten()
+            .find(|trade_good| trade_good.symbol == trip.good)
+            .map_or(trip.units, |trade_good| trade_good.trade_volume);
+
+        ship::purchase(config, ship, trip.good, trip.units, trade_volume).await?;
+    }
+
+    ship::navigate(config, ship, &trip.destination).await?;
+    ship::dock(config, ship).await?;
+    ship::refuel(config, ship).await?;
+
+    let deliver = contract.terms.deliver
+        .iter()
+        .flatten()
+        .find(|deliver| deliver.trade_symbol == trip.good.to_string())
+        .ok_or(format!("Contract {} doesn't require {}", contract.id, trip.good))?;
+    let units = ship::units_in_cargo(ship, trip.good).min(deliver.units_required - deliver.units_fulfilled);
+    if units <= 0 {
+        return Err(format!("{} has no {} to deliver", ship.symbol, trip.good))
+    }
+
+    let request = models::DeliverContractRequest::new(ship.symbol.clone(), trip.good.to_string(), units);
+    let response = retry(|| apis::contracts_api::deliver_contract(config, &contract.id, Some(request.clone())))
+        .await
+        .map_err(|err| format!("Failed to deliver {units} {}: {err}", trip.good))?;
+
+    ship.cargo = response.data.cargo;
+    let contract = *response.data.contract;
+
+    if let Some(deliver) = contract.terms.deliver.iter().flatten().find(|deliver| deliver.trade_symbol == trip.good.to_string()) {
+        log(&ship.symbol, format!(
+            "Delivered {units} {}, {}/{}",
+            trip.good, deliver.units_fulfilled, deliver.units_required
+        ));
+    }
+
+    Ok(contract)
+}
@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use space_trader_api::apis::configuration::Configuration;
+use space_trader_api::models;
+use tokio::sync::broadcast::error::RecvError;
+
+use super::daemon::{ShipControl, ShipControls};
+use super::events::{self, LogEntry};
+use super::tasks::{self, Task, TaskQueues};
+use super::{get_my_ships, log};
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+#[derive(Clone)]
+struct ServerState {
+    config: Arc<Configuration>,
+    controls: ShipControls,
+}
+
+#[derive(Serialize)]
+struct FleetShip {
+    ship: models::Ship,
+    paused: bool,
+    task: Option<Task>,
+}
+
+#[derive(Deserialize)]
+struct LogsQuery {
+    ship: Option<String>,
+}
+
+/// Serve the control API of the daemon on localhost
+pub async fn serve(port: u16, config: Arc<Configuration>, controls: ShipControls) -> Result<(), String> {
+    let app = Router::new()
+        .route("/fleet", get(get_fleet))
+        .route("/tasks", get(get_tasks))
+        .route("/logs", get(get_logs))
+        .route("/events", get(stream_events))
+        .route("/ships/:ship_symbol/tasks", post(assign_task))
+        .route("/ships/:ship_symbol/pause", post(pause_ship))
+        .route("/ships/:ship_symbol/resume", post(resume_ship))
+        .route("/ships/:ship_symbol/navigate", post(navigate_ship))
+        .with_state(ServerState { config, controls });
+
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|err| format!("Failed to listen on port {port}: {err}"))?;
+
+    log("daemon", format!("Control API listening on http://127.0.0.1:{port}"));
+    axum::serve(listener, app)
+        .await
+        .map_err(|err| format!("Control API failed: {err}"))
+}
+
+async fn get_fleet(State(state): State<ServerState>) -> ApiResult<Vec<FleetShip>> {
+    let ships = get_my_ships(&state.config)
+        .await
+        .map_err(|err| (StatusCode::BAD_GATEWAY, format!("Failed to get ships: {err}")))?;
+    let queues = TaskQueues::load().map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err))?;
+
+    let fleet = ships
+        .into_iter()
+        .map(|ship| FleetShip {
+            paused: state.controls.get(&ship.symbol).is_some_and(ShipControl::is_paused),
+            task: queues.current(&ship.symbol).cloned(),
+            ship,
+        })
+        .collect();
+
+    Ok(Json(fleet))
+}
+
+async fn get_tasks() -> ApiResult<TaskQueues> {
+    TaskQueues::load()
+        .map(Json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err))
+}
+
+async fn get_logs(Query(query): Query<LogsQuery>) -> Json<Vec<LogEntry>> {
+    Json(events::recent_logs(query.ship.as_deref()))
+}
+
+async fn assign_task(
+    State(state): State<ServerState>,
+    Path(ship_symbol): Path<String>,
+    Json(task): Json<Task>,
+) -> ApiResult<Task> {
+    tasks::push(&ship_symbol, task.clone()).map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err))?;
+    log(&ship_symbol, format!("Assigned task: {task}"));
+    // The ships bought since the daemon started aren't run yet
+    if let Some(control) = state.controls.get(&ship_symbol) {
+        control.wake();
+    }
+
+    Ok(Json(task))
+}
+
+fn ship_control<'a>(state: &'a ServerState, ship_symbol: &str) -> Result<&'a ShipControl, (StatusCode, String)> {
+    state.controls
+        .get(ship_symbol)
+        .ok_or((StatusCode::NOT_FOUND, format!("Unknown ship {ship_symbol}")))
+}
+
+async fn pause_ship(State(state): State<ServerState>, Path(ship_symbol): Path<String>) -> ApiResult<()> {
+    ship_control(&state, &ship_symbol)?.pause();
+    log(&ship_symbol, "Paused");
+
+    Ok(Json(()))
+}
+
+async fn resume_ship(State(state): State<ServerState>, Path(ship_symbol): Path<String>) -> ApiResult<()> {
+    ship_control(&state, &ship_symbol)?.resume();
+    log(&ship_symbol, "Resumed");
+
+    Ok(Json(()))
+}
+
+/// Navigate the ship right away, its current task is resumed after the arrival
+async fn navigate_ship(
+    State(state): State<ServerState>,
+    Path(ship_symbol): Path<String>,
+    Json(request): Json<models::NavigateShipRequest>,
+) -> ApiResult<Task> {
+    let control = ship_control(&state, &ship_symbol)?;
+    let task = Task::Navigate { waypoint_symbol: request.waypoint_symbol };
+
+    tasks::push_front(&ship_symbol, task.clone()).map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err))?;
+    control.interrupt();
+
+    Ok(Json(task))
+}
+
+/// Stream the events as JSON messages
+async fn stream_events(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(send_events)
+}
+
+async fn send_events(mut socket: WebSocket) {
+    let mut events = events::subscribe();
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+
+        let Ok(json) = serde_json::to_string(&event) else {
+            continue
+        };
+        if socket.send(Message::Text(json)).await.is_err() {
+            return
+        }
+    }
+}
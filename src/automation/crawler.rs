@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+
+use super::market::{distance, get_system_waypoints};
+use super::{log, retry};
+use crate::cli::{flag_value, get_token};
+use crate::store::galaxy::{self, GalaxyStore, SYSTEMS_FILE};
+use crate::symbols::{parse_waypoint_trait_symbol, system_symbol_of};
+
+/// How often the crawled waypoints are saved, the waypoints file being rewritten on every save
+const SAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// The name of the crawler in the logs
+const CRAWLER: &str = "crawler";
+
+/// `space-trader crawl --token=<token> [--limit=<systems>] [--find=<trait>]`
+///
+/// Fetch the waypoints of every system, nearest to the headquarters first,
+/// or list the crawled waypoints having a trait with `--find`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+
+    if let Some(symbol) = flag_value(args, "--find=") {
+        let symbol = parse_waypoint_trait_symbol(symbol).ok_or(format!("Unknown waypoint trait {symbol}"))?;
+        return find(&config, symbol).await
+    }
+
+    let limit = match flag_value(args, "--limit=") {
+        Some(limit) => Some(limit.parse().map_err(|_| format!("Invalid limit {limit}"))?),
+        None => None,
+    };
+
+    run(&config, limit).await
+}
+
+/// Crawl the systems not crawled yet, nearest to the headquarters first.
+/// The crawl resumes where it stopped when restarted, from the last save.
+pub async fn run(config: &Configuration, limit: Option<usize>) -> Result<(), String> {
+    let systems: Vec<models::System> = crate::store::load(SYSTEMS_FILE)?;
+    let headquarters = headquarters_position(config, &system_positions(&systems)).await?;
+    let crawled = galaxy::crawled_systems()?;
+
+    let mut queue: Vec<&models::System> = systems
+        .iter()
+        .filter(|system| !crawled.contains_key(&system.symbol))
+        .collect();
+    queue.sort_by(|a, b| distance(headquarters, (a.x, a.y)).total_cmp(&distance(headquarters, (b.x, b.y))));
+    queue.truncate(limit.unwrap_or(usize::MAX));
+    log(CRAWLER, format!("Crawling {} systems, {} already crawled", queue.len(), crawled.len()));
+
+    let mut waypoints = Vec::new();
+    let mut crawled_systems = Vec::new();
+    let mut saved_at = Instant::now();
+    for (index, system) in queue.iter().enumerate() {
+        // Systems without waypoints don't need a request
+        let fetched = match system.waypoints.is_empty() {
+            true => Ok(Vec::new()),
+            false => get_system_waypoints(config, &system.symbol).await,
+        };
+        match fetched {
+            Ok(mut system_waypoints) => {
+                waypoints.append(&mut system_waypoints);
+                crawled_systems.push(system.symbol.clone());
+            }
+            // Not marked as crawled so it's retried on the next run
+            Err(err) => log(CRAWLER, err),
+        }
+
+        if saved_at.elapsed() >= SAVE_INTERVAL || index + 1 == queue.len() {
+            galaxy::record(std::mem::take(&mut waypoints))?;
+            galaxy::mark_crawled(&std::mem::take(&mut crawled_systems))?;
+            saved_at = Instant::now();
+            log(CRAWLER, format!("Crawled {}/{} systems", index + 1, queue.len()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the crawled waypoints having a trait, nearest to the headquarters first
+async fn find(config: &Configuration, symbol: models::WaypointTraitSymbol) -> Result<(), String> {
+    let positions = system_positions(&crate::store::load::<Vec<models::System>>(SYSTEMS_FILE)?);
+    let headquarters = headquarters_position(config, &positions).await?;
+    let store = GalaxyStore::load()?;
+
+    let mut waypoints: Vec<(&models::Waypoint, f32)> = store
+        .with_trait(symbol)
+        .map(|waypoint| {
+            let position = positions.get(waypoint.system_symbol.as_str()).copied().unwrap_or(headquarters);
+            (waypoint, distance(headquarters, position))
+        })
+        .collect();
+    waypoints.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    println!("{} waypoints with {symbol} in {} crawled systems", waypoints.len(), galaxy::crawled_systems()?.len());
+    for (waypoint, distance) in waypoints {
+        println!("{:<16} {:<20} {distance:>8.0} from the headquarters", waypoint.symbol, waypoint.r#type.to_string());
+    }
+
+    Ok(())
+}
+
+/// The coordinates of the systems, by system symbol
+fn system_positions(systems: &[models::System]) -> HashMap<String, (i32, i32)> {
+    systems
+        .iter()
+        .map(|system| (system.symbol.clone(), (system.x, system.y)))
+        .collect()
+}
+
+async fn headquarters_position(config: &Configuration, positions: &HashMap<String, (i32, i32)>) -> Result<(i32, i32), String> {
+    let agent = retry(|| apis::agents_api::get_my_agent(config))
+        .await
+        .map_err(|err| format!("Failed to get agent: {err}"))?
+        .data;
+
+    let system_symbol = system_symbol_of(&agent.headquarters);
+    positions
+        .get(system_symbol)
+        .copied()
+        .ok_or(format!("Unknown headquarters system {system_symbol}, download the systems first"))
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use space_trader_api::apis::{self, configuration::Configuration};
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinSet;
+
+use super::events::{emit, Event};
+use super::tasks::{self, Task, TaskQueues};
+use super::{control, crawler, get_my_ships, log, retry, ship};
+use crate::cli::{flag_value, get_token, has_flag};
+use crate::status;
+use crate::store::{conditions, ledger};
+
+/// How long an idle ship waits before checking its task queue again
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+/// The longest wait before retrying a failed task, the wait doubling after every failure
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// The failures in a row after which a task is moved to the failed tasks
+const MAX_TASK_FAILURES: u32 = 5;
+const DEFAULT_CONTROL_PORT: u16 = 8765;
+
+/// Lets the control API pause, interrupt and wake up a ship
+pub struct ShipControl {
+    paused: watch::Sender<bool>,
+    interrupt: Notify,
+    wake: Notify,
+}
+
+pub type ShipControls = Arc<HashMap<String, ShipControl>>;
+
+impl ShipControl {
+    fn new() -> ShipControl {
+        ShipControl {
+            paused: watch::Sender::new(false),
+            interrupt: Notify::new(),
+            wake: Notify::new(),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Stop the current task until the ship is resumed
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    /// Stop the current task, the ship starts again with the first task of its queue
+    pub fn interrupt(&self) {
+        // Kept until the ship waits for it, so it's not lost between two tasks
+        self.interrupt.notify_one();
+    }
+
+    /// Check the task queue of an idle ship right away
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Wait until the ship is woken up or interrupted
+    async fn sleep(&self, duration: Duration) {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => {}
+            _ = self.wake.notified() => {}
+            _ = self.interrupt.notified() => {}
+        }
+    }
+}
+
+/// `space-trader daemon --token=<token> [--port=8765] [--crawl]`
+///
+/// Run the task queue of every ship until Ctrl+C is pressed,
+/// crawling the waypoints of the galaxy in the background with `--crawl`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Arc::new(Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?));
+    let port = match flag_value(args, "--port=") {
+        Some(port) => port.parse().map_err(|_| format!("Invalid port {port}"))?,
+        None => DEFAULT_CONTROL_PORT,
+    };
+
+    let ships = get_my_ships(&config)
+        .await
+        .map_err(|err| format!("Failed to get ships: {err}"))?;
+
+    // Records the reset the cache is built for, the ships adding to it from now on
+    match status::fetch().await.map(|server_status| status::stale_files_warning(&server_status)) {
+        Ok(Some(warning)) | Err(warning) => log("daemon", warning),
+        Ok(None) => {}
+    }
+
+    let controls: ShipControls = Arc::new(
+        ships.iter().map(|ship| (ship.symbol.clone(), ShipControl::new())).collect()
+    );
+
+    let mut ship_tasks = JoinSet::new();
+    for ship in ships {
+        ship_tasks.spawn(run_ship(config.clone(), controls.clone(), ship.symbol));
+    }
+
+    let server = tokio::spawn(control::serve(port, config.clone(), controls.clone()));
+    let crawler = has_flag(args, "--crawl").then(|| {
+        let config = config.clone();
+        tokio::spawn(async move {
+            if let Err(err) = crawler::run(&config, None).await {
+                log("crawler", err);
+            }
+        })
+    });
+
+    tokio::signal::ctrl_c()
+        .await
+        .map_err(|err| format!("Failed to listen for Ctrl+C: {err}"))?;
+
+    // The running tasks stay at the front of their queue and are resumed on restart
+    log("daemon", "Shutting down");
+    server.abort();
+    if let Some(crawler) = crawler {
+        crawler.abort();
+    }
+    ship_tasks.shutdown().await;
+
+    Ok(())
+}
+
+async fn run_ship(config: Arc<Configuration>, controls: ShipControls, ship_symbol: String) {
+    let control = &controls[&ship_symbol];
+    let mut paused = control.paused.subscribe();
+    // The current task and how many times in a row it failed
+    let mut failures: Option<(Task, u32)> = None;
+
+    loop {
+        let _ = paused.wait_for(|paused| !paused).await;
+
+        let queues = match TaskQueues::load() {
+            Ok(queues) => queues,
+            Err(err) => {
+                log(&ship_symbol, err);
+                control.sleep(IDLE_INTERVAL).await;
+                continue
+            }
+        };
+        let Some(task) = queues.current(&ship_symbol).cloned() else {
+            control.sleep(IDLE_INTERVAL).await;
+            continue
+        };
+
+        let mut ship = match retry(|| apis::fleet_api::get_my_ship(&config, &ship_symbol)).await {
+            Ok(response) => *response.data,
+            Err(err) => {
+                log(&ship_symbol, format!("Failed to get ship: {err}"));
+                control.sleep(IDLE_INTERVAL).await;
+                continue
+            }
+        };
+        if let Err(err) = conditions::record(&ship) {
+            log(&ship_symbol, err);
+        }
+
+        let run_task = async {
+            // Sleep until the ship is available instead of polling it
+            ship::wait_for_arrival(&mut ship).await;
+            ship::wait_for_cooldown(&mut ship).await;
+
+            log(&ship_symbol, format!("Starting task: {task}"));
+            task.run(&config, ship).await
+        };
+
+        let result = tokio::select! {
+            result = run_task => Some(result),
+            _ = control.interrupt.notified() => {
+                // The task stays in the queue to be resumed later
+                log(&ship_symbol, format!("Interrupted task: {task}"));
+                None
+            }
+            _ = paused.wait_for(|paused| *paused) => {
+                log(&ship_symbol, format!("Paused task: {task}"));
+                None
+            }
+        };
+
+        // The next transactions of the ship aren't part of the task anymore
+        ledger::clear_strategy(&ship_symbol);
+        let Some(result) = result else {
+            continue
+        };
+
+        match result {
+            Ok(()) => {
+                log(&ship_symbol, format!("Completed task: {task}"));
+                failures = None;
+                if let Err(err) = tasks::complete(&ship_symbol, &task) {
+                    log(&ship_symbol, err);
+                }
+            }
+            Err(err) => {
+                log(&ship_symbol, format!("Task {task} failed: {err}"));
+                emit(Event::Error { ship_symbol: ship_symbol.clone(), message: err.clone() });
+
+                let count = match &failures {
+                    Some((failed_task, count)) if *failed_task == task => count + 1,
+                    _ => 1,
+                };
+                if count >= MAX_TASK_FAILURES {
+                    log(&ship_symbol, format!("Giving up on task {task} after {count} failures"));
+                    failures = None;
+                    if let Err(err) = tasks::fail(&ship_symbol, &task, err) {
+                        log(&ship_symbol, err);
+                    }
+                    continue
+                }
+
+                // The task stays at the front of the queue, the errors are often transient
+                let wait = (IDLE_INTERVAL * 2_u32.pow(count - 1)).min(MAX_RETRY_INTERVAL);
+                log(&ship_symbol, format!("Retrying task {task} in {}s", wait.as_secs()));
+                failures = Some((task, count));
+                control.sleep(wait).await;
+            }
+        }
+    }
+}
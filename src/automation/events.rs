@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use space_trader_api::models;
+use tokio::sync::broadcast;
+
+use crate::store::ledger::LedgerEntry;
+
+/// The number of log entries kept in memory
+const LOGS_LENGTH: usize = 1000;
+
+static EVENTS: LazyLock<broadcast::Sender<Event>> = LazyLock::new(|| broadcast::channel(256).0);
+static LOGS: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+/// Something that happened to a ship, streamed to the control API clients
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event {
+    Arrival { ship_symbol: String, nav: models::ShipNav },
+    CooldownCompleted { ship_symbol: String, cooldown: models::Cooldown },
+    /// Every credit movement recorded in the ledger, from trades to ship purchases and contract payments
+    Transaction { entry: LedgerEntry },
+    Error { ship_symbol: String, message: String },
+    Log(LogEntry),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub ship_symbol: String,
+    pub message: String,
+}
+
+pub fn emit(event: Event) {
+    // Sending only fails when nobody is listening
+    let _ = EVENTS.send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    EVENTS.subscribe()
+}
+
+pub fn record_log(entry: LogEntry) {
+    {
+        let mut logs = LOGS.lock().unwrap();
+        logs.push_back(entry.clone());
+        if logs.len() > LOGS_LENGTH {
+            logs.pop_front();
+        }
+    }
+
+    emit(Event::Log(entry));
+}
+
+/// The recent log entries, of a single ship when given
+pub fn recent_logs(ship_symbol: Option<&str>) -> Vec<LogEntry> {
+    LOGS.lock()
+        .unwrap()
+        .iter()
+        .filter(|entry| ship_symbol.is_none_or(|symbol| entry.ship_symbol == symbol))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models::{self, WaypointTraitSymbol};
+
+use super::market::{distance, get_system_waypoints};
+use super::{log, retry, ship};
+use crate::cli::{flag_value, get_token};
+use crate::store::galaxy::{self, has_trait, GalaxyStore};
+use crate::store::ledger;
+use crate::symbols::system_symbol_of;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExplorationOptions {
+    /// The center of the area to chart, the ship's waypoint when not given
+    pub center: Option<String>,
+    /// The radius of the area around the center, the whole system when not given
+    pub radius: Option<f32>,
+}
+
+impl ExplorationOptions {
+    /// Parse the `--center` and `--radius` arguments
+    pub fn from_args(args: &[String]) -> Result<ExplorationOptions, String> {
+        let radius = match flag_value(args, "--radius=") {
+            Some(radius) => Some(radius.parse().map_err(|_| format!("Invalid radius {radius}"))?),
+            None => None,
+        };
+
+        Ok(ExplorationOptions {
+            center: flag_value(args, "--center=").map(str::to_string),
+            radius,
+        })
+    }
+}
+
+/// `space-trader explore --ship=<symbol> --token=<token> [--center=<waypoint>] [--radius=<distance>]`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+    let ship_symbol = flag_value(args, "--ship=").ok_or("No ship argument")?;
+    let options = ExplorationOptions::from_args(args)?;
+
+    let ship = *retry(|| apis::fleet_api::get_my_ship(&config, ship_symbol))
+        .await
+        .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))?
+        .data;
+
+    run(&config, ship, &options).await
+}
+
+/// Chart the uncharted waypoints of an area of the ship's system until none is left
+pub async fn run(config: &Configuration, mut ship: models::Ship, options: &ExplorationOptions) -> Result<(), String> {
+    ledger::set_strategy(&ship.symbol, "explore");
+    let system_symbol = ship.nav.system_symbol.clone();
+    let center_symbol = options.center.clone().unwrap_or_else(|| ship.nav.waypoint_symbol.clone());
+    if system_symbol_of(&center_symbol) != system_symbol {
+        return Err(format!("The center {center_symbol} is not in the ship's system {system_symbol}"))
+    }
+
+    // The traits of the waypoints are only known once fetched
+    galaxy::record(get_system_waypoints(config, &system_symbol).await?)?;
+
+    loop {
+        let store = GalaxyStore::load()?;
+        let center = store.waypoint(&center_symbol).ok_or(format!("Unknown waypoint {center_symbol}"))?;
+        let center = (center.x, center.y);
+
+        let uncharted: Vec<(String, (i32, i32))> = store
+            .waypoints_of(&system_symbol)
+            .filter(|waypoint| has_trait(waypoint, WaypointTraitSymbol::Uncharted))
+            .filter(|waypoint| options.radius.is_none_or(|radius| distance(center, (waypoint.x, waypoint.y)) <= radius))
+            .map(|waypoint| (waypoint.symbol.clone(), (waypoint.x, waypoint.y)))
+            .collect();
+
+        if uncharted.is_empty() {
+            log(&ship.symbol, format!("Every waypoint around {center_symbol} is charted"));
+            return Ok(())
+        }
+
+        let fuel_stations: Vec<(String, (i32, i32))> = store
+            .waypoints_of(&system_symbol)
+            .filter(|waypoint| has_trait(waypoint, WaypointTraitSymbol::Marketplace))
+            .map(|waypoint| (waypoint.symbol.clone(), (waypoint.x, waypoint.y)))
+            .collect();
+
+        let route = plan_route(position(&ship), uncharted);
+        log(&ship.symbol, format!(
+            "Charting {} waypoints: {}",
+            route.len(), route.iter().map(|(symbol, _)| symbol.as_str()).collect::<Vec<_>>().join(", ")
+        ));
+
+        let mut charted = Vec::with_capacity(route.len());
+        let result = chart_route(config, &mut ship, route, &fuel_stations, &mut charted).await;
+        // Recorded once per route, each record rewrites the whole waypoints file
+        galaxy::record(charted)?;
+        result?;
+    }
+}
+
+/// Chart the waypoints of a route in order, collecting them until one fails
+async fn chart_route(
+    config: &Configuration,
+    ship: &mut models::Ship,
+    route: Vec<(String, (i32, i32))>,
+    fuel_stations: &[(String, (i32, i32))],
+    charted: &mut Vec<models::Waypoint>,
+) -> Result<(), String> {
+    for (waypoint_symbol, waypoint_position) in route {
+        ensure_fuel(config, ship, waypoint_position, fuel_stations).await?;
+        ship::navigate(config, ship, &waypoint_symbol).await?;
+        charted.push(chart(config, ship, &waypoint_symbol).await?);
+    }
+
+    Ok(())
+}
+
+fn position(ship: &models::Ship) -> (i32, i32) {
+    (ship.nav.route.destination.x, ship.nav.route.destination.y)
+}
+
+/// The fuel used to travel in cruise mode
+fn fuel_cost(from: (i32, i32), to: (i32, i32)) -> i32 {
+    match from == to {
+        true => 0,
+        false => (distance(from, to).round() as i32).max(1),
+    }
+}
+
+/// Order the waypoints to visit with the nearest neighbour heuristic, improved by 2-opt
+pub fn plan_route(start: (i32, i32), mut waypoints: Vec<(String, (i32, i32))>) -> Vec<(String, (i32, i32))> {
+    let mut route = Vec::with_capacity(waypoints.len());
+    let mut current = start;
+    while !waypoints.is_empty() {
+        let nearest = (0..waypoints.len())
+            .min_by(|a, b| distance(current, waypoints[*a].1).total_cmp(&distance(current, waypoints[*b].1)))
+            .unwrap_or_default();
+        let waypoint = waypoints.swap_remove(nearest);
+        current = waypoint.1;
+        route.push(waypoint);
+    }
+
+    // Reverse segments of the route while it makes the route shorter
+    let position = |route: &[(String, (i32, i32))], index: usize| match index {
+        0 => start,
+        _ => route[index - 1].1,
+    };
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 0..route.len() {
+            for j in i + 1..route.len() {
+                let (a, b) = (position(&route, i), route[i].1);
+                let (c, d) = (route[j].1, route.get(j + 1).map(|waypoint| waypoint.1));
+
+                let before = distance(a, b) + d.map_or(0., |d| distance(c, d));
+                let after = distance(a, c) + d.map_or(0., |d| distance(b, d));
+                if after + 0.01 < before {
+                    route[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    route
+}
+
+/// Refuel first when the ship couldn't reach a fuel station after the next waypoint
+async fn ensure_fuel(
+    config: &Configuration,
+    ship: &mut models::Ship,
+    destination: (i32, i32),
+    fuel_stations: &[(String, (i32, i32))],
+) -> Result<(), String> {
+    // Probes and satellites don't use fuel
+    if ship.fuel.capacity == 0 {
+        return Ok(())
+    }
+
+    let reserve = fuel_stations
+        .iter()
+        .map(|(_, station)| fuel_cost(destination, *station))
+        .min()
+        .unwrap_or_default();
+    if ship.fuel.current >= fuel_cost(position(ship), destination) + reserve {
+        return Ok(())
+    }
+
+    let nearest_station = fuel_stations
+        .iter()
+        .min_by_key(|(_, station)| fuel_cost(position(ship), *station));
+    let Some((station_symbol, _)) = nearest_station else {
+        return Err(String::from("Not enough fuel and no known fuel station"))
+    };
+
+    log(&ship.symbol, format!("Refueling at {station_symbol}"));
+    ship::navigate(config, ship, station_symbol).await?;
+    ship::refuel(config, ship).await
+}
+
+/// Chart a waypoint, revealing its traits
+async fn chart(config: &Configuration, ship: &models::Ship, waypoint_symbol: &str) -> Result<models::Waypoint, String> {
+    let waypoint = match retry(|| apis::fleet_api::create_chart(config, &ship.symbol)).await {
+        Ok(response) => {
+            let traits = response.data.waypoint.traits
+                .iter()
+                .map(|waypoint_trait| waypoint_trait.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            log(&ship.symbol, format!("Charted {waypoint_symbol}: {traits}"));
+            *response.data.waypoint
+        }
+        // Another agent may have charted it since the waypoints were fetched
+        Err(err) => {
+            let system_symbol = system_symbol_of(waypoint_symbol);
+            let waypoint = *retry(|| apis::systems_api::get_waypoint(config, system_symbol, waypoint_symbol))
+                .await
+                .map_err(|err| format!("Failed to get waypoint {waypoint_symbol}: {err}"))?
+                .data;
+
+            if has_trait(&waypoint, WaypointTraitSymbol::Uncharted) {
+                return Err(format!("Failed to chart {waypoint_symbol}: {err}"))
+            }
+            waypoint
+        }
+    };
+
+    Ok(waypoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waypoints(positions: &[(i32, i32)]) -> Vec<(String, (i32, i32))> {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(index, position)| (format!("X1-A1-{index}"), *position))
+            .collect()
+    }
+
+    fn positions(route: &[(String, (i32, i32))]) -> Vec<(i32, i32)> {
+        route.iter().map(|(_, position)| *position).collect()
+    }
+
+    #[test]
+    fn no_waypoint_makes_an_empty_route() {
+        assert!(plan_route((0, 0), Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn single_waypoint_is_the_route() {
+        assert_eq!(plan_route((5, 5), waypoints(&[(-3, 8)])), waypoints(&[(-3, 8)]));
+    }
+
+    #[test]
+    fn nearest_waypoints_are_visited_first() {
+        let route = plan_route((0, 0), waypoints(&[(30, 0), (10, 0), (20, 0)]));
+        assert_eq!(positions(&route), [(10, 0), (20, 0), (30, 0)]);
+    }
+
+    #[test]
+    fn crossing_is_removed() {
+        // The nearest neighbours go down to (0, -3) first, then cross the route back up to (1, 2)
+        let route = plan_route((0, 0), waypoints(&[(0, -3), (0, -2), (1, -1), (1, 2)]));
+        assert_eq!(positions(&route), [(1, 2), (1, -1), (0, -2), (0, -3)]);
+    }
+}
@@ -0,0 +1,297 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+
+use super::market::{distance, get_waypoints_with_trait};
+use super::{get_my_ships, log, retry, ship};
+use crate::cli::{flag_value, get_token, has_flag};
+use crate::store::conditions::{self, Component, ConditionSample, ConditionStore};
+use crate::store::ledger::{self, LedgerEntry};
+use crate::store::shipyards::ShipyardStore;
+use crate::time;
+
+/// The condition under which a component is about to break, from 0 to 1
+const CRITICAL_CONDITION: f64 = 0.3;
+/// The condition under which a component should be looked after
+const WARNING_CONDITION: f64 = 0.6;
+/// How far ahead a component reaching the critical condition needs a repair
+const ADVICE_HORIZON: TimeDelta = TimeDelta::hours(24);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Advice {
+    KeepFlying,
+    Repair,
+    Scrap,
+}
+
+/// The prices quoted for a ship, only known when it's at a shipyard
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Quotes {
+    pub repair: Option<i32>,
+    pub scrap: Option<i32>,
+    /// The cheapest known price of a ship with the same frame
+    pub replacement: Option<i32>,
+}
+
+/// `space-trader maintenance --token=<token> [--ship=<symbol>] [--repair] [--scrap]`
+///
+/// Print the wear of the ships with a repair or scrap advice.
+/// The ones advised to be repaired are repaired at the nearest shipyard with `--repair`, and the ones advised to be scrapped are scrapped there with `--scrap`.
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+
+    let ships = match flag_value(args, "--ship=") {
+        Some(ship_symbol) => vec![*retry(|| apis::fleet_api::get_my_ship(&config, ship_symbol))
+            .await
+            .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))?
+            .data],
+        None => get_my_ships(&config)
+            .await
+            .map_err(|err| format!("Failed to get ships: {err}"))?,
+    };
+
+    for ship in ships {
+        conditions::record(&ship)?;
+        let store = ConditionStore::load()?;
+        let history = store.history(&ship.symbol);
+
+        println!("{} ({})", ship.symbol, ship.frame.name);
+        for component in Component::ALL {
+            let wear = ConditionSample::of(&ship).wear(component);
+            let projection = match project(history, component) {
+                Some(critical_at) if critical_at <= Utc::now() => String::from("critical"),
+                Some(critical_at) => format!("critical in {}", time::format_time_left(critical_at)),
+                None => String::from("not wearing"),
+            };
+            println!("  {component}: condition {:.2}, integrity {:.2}, {projection}", wear.condition, wear.integrity);
+        }
+
+        let quotes = get_quotes(&config, &ship).await?;
+        let (advice, reason) = advise(&ship, history, &quotes);
+        println!("  {advice:?}: {reason}");
+
+        match advice {
+            Advice::Repair if has_flag(args, "--repair") => repair(&config, ship).await?,
+            Advice::Scrap if has_flag(args, "--scrap") => scrap(&config, ship).await?,
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// When a component will reach the critical condition at its recent wear rate,
+/// from the samples since its last repair
+pub fn project(history: &[ConditionSample], component: Component) -> Option<DateTime<Utc>> {
+    let since_repair = history
+        .windows(2)
+        .rposition(|pair| pair[1].wear(component).condition > pair[0].wear(component).condition)
+        .map_or(history, |index| &history[index + 1..]);
+    let (first, last) = (since_repair.first()?, since_repair.last()?);
+
+    let current = last.wear(component).condition;
+    if current <= CRITICAL_CONDITION {
+        return Some(last.timestamp)
+    }
+
+    // Least squares slope of the condition per hour
+    let points: Vec<(f64, f64)> = since_repair
+        .iter()
+        .map(|sample| ((sample.timestamp - first.timestamp).num_seconds() as f64 / 3600., sample.wear(component).condition))
+        .collect();
+    let count = points.len() as f64;
+    let mean_hours = points.iter().map(|(hours, _)| hours).sum::<f64>() / count;
+    let mean_condition = points.iter().map(|(_, condition)| condition).sum::<f64>() / count;
+    let covariance: f64 = points.iter().map(|(hours, condition)| (hours - mean_hours) * (condition - mean_condition)).sum();
+    let variance: f64 = points.iter().map(|(hours, _)| (hours - mean_hours).powi(2)).sum();
+
+    if variance == 0. || covariance >= 0. {
+        return None
+    }
+
+    let hours_left = (CRITICAL_CONDITION - current) / (covariance / variance);
+    Some(last.timestamp + TimeDelta::seconds((hours_left * 3600.) as i64))
+}
+
+/// Whether the ship should keep flying, be repaired or scrapped, and why
+pub fn advise(ship: &models::Ship, history: &[ConditionSample], quotes: &Quotes) -> (Advice, String) {
+    let sample = ConditionSample::of(ship);
+
+    // Repairs can't bring the condition above the integrity
+    if let Some(component) = Component::ALL.into_iter().find(|component| sample.wear(*component).integrity < CRITICAL_CONDITION) {
+        let value = quotes.scrap.map_or(String::new(), |scrap| format!(", scrapping it earns {scrap} credits"));
+        return (Advice::Scrap, format!("the {component} integrity is too low to be repaired{value}"))
+    }
+
+    let horizon = Utc::now() + ADVICE_HORIZON;
+    let worn = Component::ALL.into_iter().find(|component| {
+        sample.wear(*component).condition < WARNING_CONDITION
+            || project(history, *component).is_some_and(|critical_at| critical_at < horizon)
+    });
+    let Some(component) = worn else {
+        return (Advice::KeepFlying, String::from("every component is in good condition"))
+    };
+
+    match (quotes.repair, quotes.scrap, quotes.replacement) {
+        (Some(repair), Some(scrap), Some(replacement)) if repair >= replacement - scrap => (
+            Advice::Scrap,
+            format!("repairing the {component} costs {repair} credits, scrapping earns {scrap} and a new ship costs {replacement}")
+        ),
+        (Some(repair), _, _) => (Advice::Repair, format!("the {component} is worn, repairing costs {repair} credits")),
+        (None, _, _) => (Advice::Repair, format!("the {component} is worn, go to a shipyard for a quote")),
+    }
+}
+
+/// Get the repair and scrap quotes, the API only quotes ships at a shipyard
+pub async fn get_quotes(config: &Configuration, ship: &models::Ship) -> Result<Quotes, String> {
+    let repair = retry(|| apis::fleet_api::get_repair_ship(config, &ship.symbol))
+        .await
+        .ok()
+        .map(|response| response.data.transaction.total_price);
+    let scrap = retry(|| apis::fleet_api::get_scrap_ship(config, &ship.symbol))
+        .await
+        .ok()
+        .map(|response| response.data.transaction.total_price);
+    let replacement = ShipyardStore::load()?
+        .ships()
+        .filter(|shipyard_ship| shipyard_ship.frame.symbol == ship.frame.symbol)
+        .map(|shipyard_ship| shipyard_ship.purchase_price)
+        .min();
+
+    Ok(Quotes { repair, scrap, replacement })
+}
+
+/// Navigate to the nearest shipyard of the system and dock there
+async fn dock_at_nearest_shipyard(config: &Configuration, ship: &mut models::Ship) -> Result<(), String> {
+    let position = (ship.nav.route.destination.x, ship.nav.route.destination.y);
+    let shipyard = get_waypoints_with_trait(config, &ship.nav.system_symbol, models::WaypointTraitSymbol::Shipyard)
+        .await?
+        .into_iter()
+        .min_by(|a, b| distance(position, (a.x, a.y)).total_cmp(&distance(position, (b.x, b.y))))
+        .ok_or(format!("No shipyard in {}", ship.nav.system_symbol))?;
+
+    ship::navigate(config, ship, &shipyard.symbol).await?;
+    ship::dock(config, ship).await
+}
+
+/// Navigate to the nearest shipyard of the system and repair the ship
+pub async fn repair(config: &Configuration, mut ship: models::Ship) -> Result<(), String> {
+    dock_at_nearest_shipyard(config, &mut ship).await?;
+    ship::refuel(config, &mut ship).await?;
+
+    let response = retry(|| apis::fleet_api::repair_ship(config, &ship.symbol))
+        .await
+        .map_err(|err| format!("Failed to repair {}: {err}", ship.symbol))?;
+
+    log(&ship.symbol, format!("Repaired for {} credits", response.data.transaction.total_price));
+    ledger::record(LedgerEntry::from_repair(&response.data.transaction, Some(&response.data.agent)));
+    conditions::record(&response.data.ship)
+}
+
+/// Navigate to the nearest shipyard of the system and scrap the ship
+pub async fn scrap(config: &Configuration, mut ship: models::Ship) -> Result<(), String> {
+    dock_at_nearest_shipyard(config, &mut ship).await?;
+
+    let response = retry(|| apis::fleet_api::scrap_ship(config, &ship.symbol))
+        .await
+        .map_err(|err| format!("Failed to scrap {}: {err}", ship.symbol))?;
+
+    log(&ship.symbol, format!("Scrapped for {} credits", response.data.transaction.total_price));
+    ledger::record(LedgerEntry::from_scrap(&response.data.transaction, Some(&response.data.agent)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::conditions::Wear;
+
+    fn sample(timestamp: DateTime<Utc>, condition: f64) -> ConditionSample {
+        let wear = Wear { condition, integrity: 1. };
+        ConditionSample { timestamp, frame: wear, reactor: wear, engine: wear }
+    }
+
+    /// Samples taken an hour apart, the last one now
+    fn history(conditions: &[f64]) -> Vec<ConditionSample> {
+        let now = Utc::now();
+        conditions
+            .iter()
+            .enumerate()
+            .map(|(index, condition)| sample(now - TimeDelta::hours((conditions.len() - 1 - index) as i64), *condition))
+            .collect()
+    }
+
+    fn ship(condition: f64, integrity: f64) -> models::Ship {
+        let mut ship = models::Ship::default();
+        (ship.frame.condition, ship.frame.integrity) = (condition, integrity);
+        (ship.reactor.condition, ship.reactor.integrity) = (1., 1.);
+        (ship.engine.condition, ship.engine.integrity) = (1., 1.);
+        ship
+    }
+
+    #[test]
+    fn degrading_condition_is_projected_to_the_critical_condition() {
+        let history = history(&[0.9, 0.8, 0.7]);
+        let critical_at = project(&history, Component::Frame).unwrap();
+
+        // Losing 0.1 per hour, from 0.7 to 0.3
+        let expected = history[2].timestamp + TimeDelta::hours(4);
+        assert!((critical_at - expected).num_seconds().abs() <= 1, "{critical_at} isn't {expected}");
+    }
+
+    #[test]
+    fn steady_or_improving_condition_is_not_projected() {
+        assert_eq!(project(&history(&[0.8, 0.8, 0.8]), Component::Frame), None);
+        assert_eq!(project(&history(&[0.7, 0.8, 0.9]), Component::Engine), None);
+    }
+
+    #[test]
+    fn projection_starts_after_the_last_repair() {
+        assert_eq!(project(&history(&[0.9, 0.6, 1., 1.]), Component::Reactor), None);
+    }
+
+    #[test]
+    fn too_few_samples_are_not_projected() {
+        assert_eq!(project(&[], Component::Frame), None);
+        assert_eq!(project(&history(&[0.8]), Component::Frame), None);
+    }
+
+    #[test]
+    fn critical_condition_is_reached_at_the_last_sample() {
+        let history = history(&[0.5, 0.2]);
+        assert_eq!(project(&history, Component::Frame), Some(history[1].timestamp));
+    }
+
+    #[test]
+    fn low_integrity_is_scrapped() {
+        let (advice, _) = advise(&ship(0.9, 0.2), &[], &Quotes::default());
+        assert_eq!(advice, Advice::Scrap);
+    }
+
+    #[test]
+    fn good_condition_keeps_flying() {
+        let (advice, _) = advise(&ship(0.9, 1.), &history(&[0.9, 0.9]), &Quotes::default());
+        assert_eq!(advice, Advice::KeepFlying);
+    }
+
+    #[test]
+    fn worn_component_is_repaired_with_or_without_a_quote() {
+        let quotes = Quotes { repair: Some(1000), scrap: Some(2000), replacement: Some(50000) };
+
+        assert_eq!(advise(&ship(0.5, 1.), &[], &quotes).0, Advice::Repair);
+        assert_eq!(advise(&ship(0.5, 1.), &[], &Quotes::default()).0, Advice::Repair);
+    }
+
+    #[test]
+    fn component_projected_to_break_soon_is_repaired() {
+        // Above the warning condition, but losing 0.05 per hour
+        let (advice, _) = advise(&ship(0.7, 1.), &history(&[0.8, 0.75, 0.7]), &Quotes::default());
+        assert_eq!(advice, Advice::Repair);
+    }
+
+    #[test]
+    fn worn_component_costing_more_than_a_replacement_is_scrapped() {
+        let quotes = Quotes { repair: Some(45000), scrap: Some(10000), replacement: Some(50000) };
+        assert_eq!(advise(&ship(0.5, 1.), &[], &quotes).0, Advice::Scrap);
+    }
+}
@@ -0,0 +1,72 @@
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+
+use super::retry;
+use crate::store::markets::MarketStore;
+
+/// Get all the waypoints of a system having the marketplace trait
+pub async fn get_marketplaces(config: &Configuration, system_symbol: &str) -> Result<Vec<models::Waypoint>, String> {
+    get_waypoints_with_trait(config, system_symbol, models::WaypointTraitSymbol::Marketplace).await
+}
+
+/// Get all the waypoints of a system having a trait
+pub async fn get_waypoints_with_trait(
+    config: &Configuration,
+    system_symbol: &str,
+    waypoint_trait: models::WaypointTraitSymbol,
+) -> Result<Vec<models::Waypoint>, String> {
+    let traits = models::GetSystemWaypointsTraitsParameter::WaypointTraitSymbol(waypoint_trait);
+    get_waypoints(config, system_symbol, Some(traits))
+        .await
+        .map_err(|err| format!("Failed to get the {waypoint_trait} waypoints of {system_symbol}: {err}"))
+}
+
+/// Get all the waypoints of a system
+pub async fn get_system_waypoints(config: &Configuration, system_symbol: &str) -> Result<Vec<models::Waypoint>, String> {
+    get_waypoints(config, system_symbol, None)
+        .await
+        .map_err(|err| format!("Failed to get the waypoints of {system_symbol}: {err}"))
+}
+
+async fn get_waypoints(
+    config: &Configuration,
+    system_symbol: &str,
+    traits: Option<models::GetSystemWaypointsTraitsParameter>,
+) -> Result<Vec<models::Waypoint>, apis::Error<apis::systems_api::GetSystemWaypointsError>> {
+    let mut waypoints = Vec::new();
+    let mut page = 1;
+
+    loop {
+        let mut response = retry(|| apis::systems_api::get_system_waypoints(
+            config,
+            system_symbol,
+            Some(page),
+            Some(20),
+            None,
+            traits.clone(),
+        ))
+            .await?;
+
+        waypoints.append(&mut response.data);
+        if response.meta.page * response.meta.limit >= response.meta.total {
+            return Ok(waypoints)
+        }
+        page += 1;
+    }
+}
+
+/// Get a market and record it in the store
+pub async fn fetch_market(config: &Configuration, store: &mut MarketStore, waypoint_symbol: &str) -> Result<models::Market, String> {
+    let system_symbol = crate::symbols::system_symbol_of(waypoint_symbol);
+    let market = retry(|| apis::systems_api::get_market(config, system_symbol, waypoint_symbol))
+        .await
+        .map_err(|err| format!("Failed to get the market of {waypoint_symbol}: {err}"))?
+        .data;
+
+    store.record(*market.clone())?;
+    Ok(*market)
+}
+
+pub fn distance(a: (i32, i32), b: (i32, i32)) -> f32 {
+    (((a.0 - b.0).pow(2) + (a.1 - b.1).pow(2)) as f32).sqrt()
+}
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models::{self, ship_mount::Symbol as MountSymbol};
+
+use super::market::{distance, fetch_market, get_marketplaces};
+use super::{error_code, log, retry, ship};
+use crate::cli::{flag_value, get_token};
+use crate::store::{conditions, ledger};
+use crate::store::markets::{self, MarketStore};
+use crate::store::surveys::{self, SurveyStore};
+use crate::symbols::{parse_trade_symbol, system_symbol_of};
+
+/// The condition under which the ship stops mining, from 0 to 1
+const DEFAULT_MIN_CONDITION: f64 = 0.5;
+/// The error codes of extractions with a survey that can't be used anymore
+pub const INVALID_SURVEY_ERROR_CODES: [i64; 3] = [
+    4221, // Survey verification error
+    4222, // Survey expired
+    4224, // Survey exhausted
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MiningOptions {
+    /// The asteroid field to mine, the ship's waypoint when not given
+    pub waypoint_symbol: Option<String>,
+    /// The goods to keep, every good a market of the system buys is kept when empty
+    pub keep: Vec<models::TradeSymbol>,
+    pub min_condition: f64,
+}
+
+impl MiningOptions {
+    /// Parse the `--waypoint`, `--keep` and `--min-condition` arguments
+    pub fn from_args(args: &[String]) -> Result<MiningOptions, String> {
+        let keep = match flag_value(args, "--keep=") {
+            Some(goods) => goods
+                .split(',')
+                .map(|good| parse_trade_symbol(good).ok_or(format!("Unknown trade symbol {good}")))
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+        let min_condition = match flag_value(args, "--min-condition=") {
+            Some(condition) => condition.parse().map_err(|_| format!("Invalid condition {condition}"))?,
+            None => DEFAULT_MIN_CONDITION,
+        };
+
+        Ok(MiningOptions {
+            waypoint_symbol: flag_value(args, "--waypoint=").map(str::to_string),
+            keep,
+            min_condition,
+        })
+    }
+}
+
+/// `space-trader mine --ship=<symbol> --token=<token> [--waypoint=<asteroid>] [--keep=IRON_ORE,COPPER_ORE] [--min-condition=0.5]`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+    let ship_symbol = flag_value(args, "--ship=").ok_or("No ship argument")?;
+    let options = MiningOptions::from_args(args)?;
+
+    let ship = *retry(|| apis::fleet_api::get_my_ship(&config, ship_symbol))
+        .await
+        .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))?
+        .data;
+
+    run(&config, ship, &options).await
+}
+
+/// Mine an asteroid field until the ship's condition falls below the threshold
+pub async fn run(config: &Configuration, mut ship: models::Ship, options: &MiningOptions) -> Result<(), String> {
+    ledger::set_strategy(&ship.symbol, "mine");
+    let mut store = MarketStore::load()?;
+    let waypoint_symbol = options.waypoint_symbol.clone().unwrap_or_else(|| ship.nav.waypoint_symbol.clone());
+    let marketplaces = get_marketplaces(config, &ship.nav.system_symbol).await?;
+
+    // The goods bought by the markets decide what's kept in the cargo
+    for waypoint in &marketplaces {
+        if store.latest(&waypoint.symbol).is_none() {
+            fetch_market(config, &mut store, &waypoint.symbol).await?;
+        }
+    }
+
+    log(&ship.symbol, format!("Mining at {waypoint_symbol}"));
+
+    loop {
+        if ship::condition(&ship) < options.min_condition {
+            log(&ship.symbol, format!("Condition fell below {}, stopping", options.min_condition));
+            return Ok(())
+        }
+
+        if ship.cargo.units >= ship.cargo.capacity {
+            sell_cargo(config, &mut store, &mut ship, &marketplaces).await?;
+        }
+
+        ship::navigate(config, &mut ship, &waypoint_symbol).await?;
+        ship::orbit(config, &mut ship).await?;
+        ship::wait_for_cooldown(&mut ship).await;
+
+        // Other ships may have surveyed the field too
+        let mut survey_store = SurveyStore::load()?;
+        if survey_store.at(&waypoint_symbol).next().is_none() && can_survey(&ship) {
+            let response = retry(|| apis::fleet_api::create_survey(config, &ship.symbol))
+                .await
+                .map_err(|err| format!("Failed to survey {waypoint_symbol}: {err}"))?;
+
+            log(&ship.symbol, format!("Created {} surveys", response.data.surveys.len()));
+            surveys::add(response.data.surveys)?;
+            survey_store = SurveyStore::load()?;
+            ship.cooldown = response.data.cooldown;
+            ship::wait_for_cooldown(&mut ship).await;
+        }
+
+        let best_survey = survey_store
+            .at(&waypoint_symbol)
+            .max_by(|a, b| survey_value(&store, a).total_cmp(&survey_value(&store, b)))
+            .cloned();
+
+        let response = match &best_survey {
+            Some(survey) => retry(|| apis::fleet_api::extract_resources_with_survey(config, &ship.symbol, Some(survey.clone())))
+                .await
+                .map_err(|err| (error_code(&err), err.to_string())),
+            None => retry(|| apis::fleet_api::extract_resources(config, &ship.symbol, None))
+                .await
+                .map_err(|err| (error_code(&err), err.to_string())),
+        };
+
+        let data = match (response, best_survey) {
+            (Ok(response), _) => response.data,
+            (Err((Some(code), err)), Some(survey)) if INVALID_SURVEY_ERROR_CODES.contains(&code) => {
+                log(&ship.symbol, format!("Dropping survey {}: {err}", survey.signature));
+                surveys::remove(&survey.signature)?;
+                continue
+            }
+            (Err((_, err)), _) => return Err(format!("Failed to extract resources: {err}")),
+        };
+
+        let extracted = &data.extraction.r#yield;
+        log(&ship.symbol, format!(
+            "Extracted {} {} ({}/{})",
+            extracted.units, extracted.symbol, data.cargo.units, data.cargo.capacity
+        ));
+        ship.cargo = data.cargo;
+        ship.cooldown = data.cooldown;
+
+        // Condition events damage the ship, get its new condition
+        if !data.events.is_empty() {
+            ship = *retry(|| apis::fleet_api::get_my_ship(config, &ship.symbol))
+                .await
+                .map_err(|err| format!("Failed to get ship {}: {err}", ship.symbol))?
+                .data;
+            conditions::record(&ship)?;
+        }
+
+        jettison_unwanted(config, &mut ship, &store, &marketplaces, options).await?;
+    }
+}
+
+/// The expected value of a unit extracted with the survey.
+/// Every deposit has the same chance to be extracted.
+pub fn survey_value(store: &MarketStore, survey: &models::Survey) -> f64 {
+    if survey.deposits.is_empty() {
+        return 0.
+    }
+
+    let system_symbol = system_symbol_of(&survey.symbol);
+    let total: i32 = survey.deposits
+        .iter()
+        .filter_map(|deposit| parse_trade_symbol(&deposit.symbol))
+        .filter_map(|good| store.best_sell_price(system_symbol, good))
+        .sum();
+
+    total as f64 / survey.deposits.len() as f64
+}
+
+fn can_survey(ship: &models::Ship) -> bool {
+    ship.mounts.iter().any(|mount| matches!(
+        mount.symbol,
+        MountSymbol::SurveyorI | MountSymbol::SurveyorIi | MountSymbol::SurveyorIii
+    ))
+}
+
+/// Whether the good is worth keeping in the cargo
+fn is_wanted(
+    good: models::TradeSymbol,
+    store: &MarketStore,
+    marketplaces: &[models::Waypoint],
+    options: &MiningOptions,
+) -> bool {
+    if !options.keep.is_empty() {
+        return options.keep.contains(&good)
+    }
+
+    marketplaces.iter().any(|waypoint| {
+        store.latest(&waypoint.symbol).is_some_and(|snapshot| markets::buys(&snapshot.market, good))
+    })
+}
+
+async fn jettison_unwanted(
+    config: &Configuration,
+    ship: &mut models::Ship,
+    store: &MarketStore,
+    marketplaces: &[models::Waypoint],
+    options: &MiningOptions,
+) -> Result<(), String> {
+    let unwanted: Vec<(models::TradeSymbol, i32)> = ship.cargo.inventory
+        .iter()
+        .filter(|item| !is_wanted(item.symbol, store, marketplaces, options))
+        .map(|item| (item.symbol, item.units))
+        .collect();
+
+    for (good, units) in unwanted {
+        ship::jettison(config, ship, good, units).await?;
+    }
+
+    Ok(())
+}
+
+/// Sell the cargo at the market buying most of it, the nearest one on a tie
+async fn sell_cargo(
+    config: &Configuration,
+    store: &mut MarketStore,
+    ship: &mut models::Ship,
+    marketplaces: &[models::Waypoint],
+) -> Result<(), String> {
+    let position = (ship.nav.route.destination.x, ship.nav.route.destination.y);
+
+    let mut best_market = None;
+    for waypoint in marketplaces {
+        let market = match store.latest(&waypoint.symbol) {
+            Some(snapshot) => snapshot.market.clone(),
+            None => fetch_market(config, store, &waypoint.symbol).await?,
+        };
+
+        let sellable_units: i32 = ship.cargo.inventory
+            .iter()
+            .filter(|item| markets::buys(&market, item.symbol))
+            .map(|item| item.units)
+            .sum();
+        let distance = distance(position, (waypoint.x, waypoint.y));
+
+        let is_better = best_market.as_ref().is_none_or(|(_, best_units, best_distance)| {
+            sellable_units > *best_units || (sellable_units == *best_units && distance < *best_distance)
+        });
+        if sellable_units > 0 && is_better {
+            best_market = Some((waypoint.symbol.clone(), sellable_units, distance));
+        }
+    }
+
+    let Some((market_symbol, _, _)) = best_market else {
+        return Err(String::from("No market of the system buys the cargo"))
+    };
+
+    ship::navigate(config, ship, &market_symbol).await?;
+    ship::dock(config, ship).await?;
+    ship::refuel(config, ship).await?;
+
+    // Prices and trade volumes are only known when the ship is at the market
+    let market = fetch_market(config, store, &market_symbol).await?;
+    for trade_good in market.trade_goods.iter().flatten() {
+        let units = ship::units_in_cargo(ship, trade_good.symbol);
+        if units > 0 && markets::buys(&market, trade_good.symbol) {
+            ship::sell(config, ship, trade_good.symbol, units, trade_good.trade_volume).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use space_trader_api::apis::configuration::Configuration;
+use space_trader_api::models::{self, ship_frame::Symbol as FrameSymbol, ShipRole};
+use tokio::task::JoinSet;
+
+use super::explorer::plan_route;
+use super::market::{fetch_market, get_marketplaces};
+use super::tasks::{self, Task};
+use super::{get_my_ships, log, ship};
+use crate::cli::{flag_value, get_token, has_flag};
+use crate::store::markets::MarketStore;
+
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProbeOptions {
+    /// The marketplaces to refresh in order, the probe stays at the market when there's only one
+    pub markets: Vec<String>,
+    /// How often every market is refreshed
+    pub interval_seconds: u64,
+}
+
+/// `space-trader probes --token=<token> [--interval=600] [--assign]`
+///
+/// Spread the probes and satellites over the marketplaces of their systems and refresh the markets,
+/// or queue the probe tasks for the daemon with `--assign`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+    let interval_seconds = match flag_value(args, "--interval=") {
+        Some(interval) => interval.parse().map_err(|_| format!("Invalid interval {interval}"))?,
+        None => DEFAULT_REFRESH_INTERVAL.as_secs(),
+    };
+
+    let probes: Vec<models::Ship> = get_my_ships(&config)
+        .await
+        .map_err(|err| format!("Failed to get ships: {err}"))?
+        .into_iter()
+        .filter(is_probe)
+        .collect();
+    if probes.is_empty() {
+        return Err(String::from("No probe or satellite in the fleet"))
+    }
+
+    let assignments = assign(&config, &probes).await?;
+
+    let mut probe_tasks = JoinSet::new();
+    for ship in probes {
+        let Some(markets) = assignments.get(&ship.symbol).cloned() else {
+            log(&ship.symbol, "No marketplace left to watch");
+            continue
+        };
+        let options = ProbeOptions { markets, interval_seconds };
+
+        if has_flag(args, "--assign") {
+            let task = Task::Probe(options);
+            println!("Assigned {task} to {}", ship.symbol);
+            tasks::push(&ship.symbol, task)?;
+        } else {
+            let config = config.clone();
+            probe_tasks.spawn(async move { run(&config, ship, &options).await });
+        }
+    }
+
+    while let Some(result) = probe_tasks.join_next().await {
+        result.map_err(|err| format!("Probe stopped: {err}"))??;
+    }
+
+    Ok(())
+}
+
+/// Probes and satellites are cheap ships without cargo to trade
+pub fn is_probe(ship: &models::Ship) -> bool {
+    ship.frame.symbol == FrameSymbol::Probe || ship.registration.role == ShipRole::Satellite
+}
+
+/// How much a market is worth watching: the number of goods it trades,
+/// imports and exports count double as their prices move the most
+pub fn trading_value(market: &models::Market) -> usize {
+    2 * (market.imports.len() + market.exports.len()) + market.exchange.len()
+}
+
+/// Give each probe the marketplaces of its system, the most valuable ones first.
+/// The markets are spread over the probes, which rotate through them when there are more markets than probes.
+async fn assign(config: &Configuration, probes: &[models::Ship]) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut store = MarketStore::load()?;
+    let mut systems: HashMap<&str, Vec<&models::Ship>> = HashMap::new();
+    for probe in probes {
+        systems.entry(&probe.nav.system_symbol).or_default().push(probe);
+    }
+
+    let mut assignments = HashMap::new();
+    for (system_symbol, system_probes) in systems {
+        let mut marketplaces = Vec::new();
+        for waypoint in get_marketplaces(config, system_symbol).await? {
+            let value = match store.latest(&waypoint.symbol) {
+                Some(snapshot) => trading_value(&snapshot.market),
+                None => trading_value(&fetch_market(config, &mut store, &waypoint.symbol).await?),
+            };
+            marketplaces.push((value, waypoint.symbol, (waypoint.x, waypoint.y)));
+        }
+        marketplaces.sort_by_key(|(value, _, _)| std::cmp::Reverse(*value));
+
+        let mut shares: Vec<Vec<(String, (i32, i32))>> = vec![Vec::new(); system_probes.len()];
+        for (index, (_, symbol, position)) in marketplaces.into_iter().enumerate() {
+            shares[index % system_probes.len()].push((symbol, position));
+        }
+
+        for (probe, share) in system_probes.into_iter().zip(shares) {
+            if share.is_empty() {
+                continue
+            }
+            let start = (probe.nav.route.destination.x, probe.nav.route.destination.y);
+            let route = plan_route(start, share).into_iter().map(|(symbol, _)| symbol).collect();
+            assignments.insert(probe.symbol.clone(), route);
+        }
+    }
+
+    Ok(assignments)
+}
+
+/// Refresh the markets forever, every interval
+pub async fn run(config: &Configuration, mut ship: models::Ship, options: &ProbeOptions) -> Result<(), String> {
+    let mut store = MarketStore::load()?;
+    let interval = Duration::from_secs(options.interval_seconds);
+
+    loop {
+        let started = tokio::time::Instant::now();
+
+        for market_symbol in &options.markets {
+            ship::navigate(config, &mut ship, market_symbol).await?;
+            let market = fetch_market(config, &mut store, market_symbol).await?;
+            log(&ship.symbol, format!(
+                "Refreshed {market_symbol} ({} goods priced)",
+                market.trade_goods.as_ref().map_or(0, Vec::len)
+            ));
+        }
+
+        tokio::time::sleep_until(started + interval).await;
+    }
+}
@@ -0,0 +1,106 @@
+use chrono::Utc;
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+
+use super::{log, retry, ship};
+use crate::cli::{flag_value, get_token, has_flag};
+use crate::store::galaxy::{self, GalaxyStore};
+use crate::store::scans::{self, Scan, ScanKind, ScanResults};
+
+/// `space-trader scan --ship=<symbol> --token=<token> [--kind=systems|waypoints|ships] [--repeat]`
+///
+/// Scan around the ship, again every time the sensors cool down with `--repeat`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+    let ship_symbol = flag_value(args, "--ship=").ok_or("No ship argument")?;
+    let kind = match flag_value(args, "--kind=") {
+        Some(kind) => ScanKind::parse(kind).ok_or(format!("Invalid scan kind {kind}"))?,
+        None => ScanKind::Ships,
+    };
+
+    let mut ship = *retry(|| apis::fleet_api::get_my_ship(&config, ship_symbol))
+        .await
+        .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))?
+        .data;
+
+    loop {
+        let scan = scan(&config, &mut ship, kind).await?;
+        for line in scan.results.describe() {
+            println!("  {line}");
+        }
+
+        if !has_flag(args, "--repeat") {
+            return Ok(())
+        }
+    }
+}
+
+/// Scan around the ship once its sensors cooled down, and store the results
+pub async fn scan(config: &Configuration, ship: &mut models::Ship, kind: ScanKind) -> Result<Scan, String> {
+    ship::wait_for_cooldown(ship).await;
+
+    let (results, cooldown) = match kind {
+        ScanKind::Systems => {
+            let data = retry(|| apis::fleet_api::create_ship_system_scan(config, &ship.symbol))
+                .await
+                .map_err(|err| format!("Failed to scan the systems around {}: {err}", ship.symbol))?
+                .data;
+            (ScanResults::Systems(data.systems), data.cooldown)
+        }
+        ScanKind::Waypoints => {
+            let data = retry(|| apis::fleet_api::create_ship_waypoint_scan(config, &ship.symbol))
+                .await
+                .map_err(|err| format!("Failed to scan the waypoints around {}: {err}", ship.symbol))?
+                .data;
+            (ScanResults::Waypoints(data.waypoints), data.cooldown)
+        }
+        ScanKind::Ships => {
+            let data = retry(|| apis::fleet_api::create_ship_ship_scan(config, &ship.symbol))
+                .await
+                .map_err(|err| format!("Failed to scan the ships around {}: {err}", ship.symbol))?
+                .data;
+            (ScanResults::Ships(data.ships), data.cooldown)
+        }
+    };
+    ship.cooldown = cooldown;
+
+    if let ScanResults::Waypoints(waypoints) = &results {
+        reveal(waypoints)?;
+    }
+
+    log(&ship.symbol, format!("Scanned {} {}", results.count(), kind.to_string().to_lowercase()));
+    let scan = Scan {
+        timestamp: Utc::now(),
+        ship_symbol: ship.symbol.clone(),
+        waypoint_symbol: ship.nav.waypoint_symbol.clone(),
+        results,
+    };
+    scans::record(scan.clone())?;
+
+    Ok(scan)
+}
+
+/// Add the scanned waypoints we didn't know to the galaxy store
+fn reveal(waypoints: &[models::ScannedWaypoint]) -> Result<(), String> {
+    let store = GalaxyStore::load()?;
+    let revealed = waypoints
+        .iter()
+        .filter(|waypoint| store.waypoint(&waypoint.symbol).is_none())
+        .map(|waypoint| models::Waypoint {
+            faction: waypoint.faction.clone(),
+            chart: waypoint.chart.clone(),
+            ..models::Waypoint::new(
+                waypoint.symbol.clone(),
+                waypoint.r#type,
+                waypoint.system_symbol.clone(),
+                waypoint.x,
+                waypoint.y,
+                waypoint.orbitals.clone(),
+                waypoint.traits.clone(),
+                false,
+            )
+        })
+        .collect();
+
+    galaxy::record(revealed)
+}
@@ -0,0 +1,454 @@
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+use rhai::{Dynamic, Engine, EvalAltResult, Position};
+use serde::Serialize;
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+use tokio::runtime::Handle;
+use tokio::sync::watch;
+
+use super::market::{fetch_market, get_marketplaces};
+use super::mining::INVALID_SURVEY_ERROR_CODES;
+use super::{error_code, get_contracts, log, retry, ship};
+use crate::cli::{flag_value, get_token};
+use crate::store::ledger::{self, LedgerEntry};
+use crate::store::markets::MarketStore;
+use crate::store::surveys::{self, SurveyStore};
+use crate::symbols::parse_trade_symbol;
+
+const LOGS_DIRECTORY: &str = "./logs";
+/// How often the script file is checked for changes
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Stops the scripts stuck in a loop without calling the API, the bindings waiting for the API most of the time
+const MAX_OPERATIONS: u64 = 100_000_000;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// `space-trader script --ship=<symbol> --script=<path> --token=<token>`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let config = Configuration::from_bearer_access_token(get_token(args).ok_or("No token argument")?);
+    let ship_symbol = flag_value(args, "--ship=").ok_or("No ship argument")?;
+    let path = flag_value(args, "--script=").ok_or("No script argument")?;
+
+    let ship = *retry(|| apis::fleet_api::get_my_ship(&config, ship_symbol))
+        .await
+        .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))?
+        .data;
+
+    run(&config, ship, path).await
+}
+
+/// Run a Rhai script controlling a ship until it ends.
+/// The script is restarted when its file changes.
+pub async fn run(config: &Configuration, mut ship: models::Ship, path: &str) -> Result<(), String> {
+    ledger::set_strategy(&ship.symbol, format!("script {}", script_name(path)));
+
+    loop {
+        let source = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read script {path}: {err}"))?;
+        let modified = modified_time(path);
+        let ship_symbol = ship.symbol.clone();
+        let (stop, stop_receiver) = watch::channel(false);
+        let (running, stopped) = mpsc::channel::<()>();
+
+        let mut script = tokio::task::spawn_blocking({
+            let config = config.clone();
+            let log_path = log_path(path, &ship_symbol);
+            let runtime = Handle::current();
+
+            move || {
+                // Disconnects the guard's receiver once the script stopped
+                let _running = running;
+                let context = ScriptContext {
+                    config,
+                    ship: Rc::new(RefCell::new(ship)),
+                    log_path,
+                    runtime,
+                    stop: stop_receiver,
+                };
+                run_script(context, &source)
+            }
+        });
+        let stop_guard = StopGuard { stop, stopped };
+
+        loop {
+            tokio::select! {
+                res = &mut script => {
+                    return res.map_err(|err| format!("Script {path} panicked: {err}"))?
+                }
+                _ = tokio::time::sleep(RELOAD_CHECK_INTERVAL) => {
+                    if modified_time(path) != modified {
+                        stop_guard.stop.send_replace(true);
+                        break
+                    }
+                }
+            }
+        }
+
+        // Wait for the script to stop before starting the new version
+        let _ = script.await;
+        log(&ship_symbol, format!("Reloading script {path}"));
+
+        ship = *retry(|| apis::fleet_api::get_my_ship(config, &ship_symbol))
+            .await
+            .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))?
+            .data;
+    }
+}
+
+/// Stops the script when the task running it is dropped, when it's interrupted for example.
+/// The script is waited for, so it doesn't keep commanding the ship during the next task.
+struct StopGuard {
+    stop: watch::Sender<bool>,
+    /// Disconnected once the script stopped
+    stopped: mpsc::Receiver<()>,
+}
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        self.stop.send_replace(true);
+        // The bindings are cancelled by the stop signal, so the script stops at its next operation
+        tokio::task::block_in_place(|| {
+            let _ = self.stopped.recv();
+        });
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Every script has its own log file per ship, like `logs/miner-SHIP-1.log`
+fn script_name(script_path: &str) -> String {
+    Path::new(script_path)
+        .file_stem()
+        .map_or(String::from("script"), |stem| stem.to_string_lossy().to_string())
+}
+
+fn log_path(script_path: &str, ship_symbol: &str) -> String {
+    format!("{LOGS_DIRECTORY}/{}-{ship_symbol}.log", script_name(script_path))
+}
+
+/// What the bindings of a script can access
+#[derive(Clone)]
+struct ScriptContext {
+    config: Configuration,
+    /// The only ship the script controls.
+    /// Not `Send`, the script and its bindings stay on the same thread.
+    ship: Rc<RefCell<models::Ship>>,
+    log_path: String,
+    runtime: Handle,
+    /// Set when the script must stop, because its file changed or its task was stopped
+    stop: watch::Receiver<bool>,
+}
+
+impl ScriptContext {
+    fn log(&self, message: &str) {
+        let ship_symbol = self.ship.borrow().symbol.clone();
+        log(&ship_symbol, message);
+
+        let line = format!("[{}] {message}\n", chrono::Utc::now().to_rfc3339());
+        let written = std::fs::create_dir_all(LOGS_DIRECTORY).and_then(|_| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.log_path)?
+                .write_all(line.as_bytes())
+        });
+        if let Err(err) = written {
+            log(&ship_symbol, format!("Failed to write to {}: {err}", self.log_path));
+        }
+    }
+
+    fn is_stopped(&self) -> bool {
+        *self.stop.borrow()
+    }
+
+    /// Run an async action on the script's ship
+    fn with_ship<T, F>(&self, action: F) -> ScriptResult<T>
+    where
+        F: AsyncFnOnce(&Configuration, &mut models::Ship) -> Result<T, String>,
+    {
+        let mut ship = self.ship.borrow_mut();
+        self.block_on(action(&self.config, &mut ship))
+    }
+
+    /// Wait for a future, terminating the script instead when it must stop
+    fn block_on<T>(&self, future: impl std::future::Future<Output = Result<T, String>>) -> ScriptResult<T> {
+        let mut stop = self.stop.clone();
+        self.runtime.block_on(async {
+            tokio::select! {
+                res = future => res.map_err(Into::into),
+                _ = stop.wait_for(|stop| *stop) => Err(EvalAltResult::ErrorTerminated(Dynamic::UNIT, Position::NONE).into()),
+            }
+        })
+    }
+}
+
+fn to_dynamic(value: &impl Serialize) -> ScriptResult<Dynamic> {
+    rhai::serde::to_dynamic(value)
+}
+
+fn parse_good(good: &str) -> ScriptResult<models::TradeSymbol> {
+    parse_trade_symbol(good).ok_or_else(|| format!("Unknown trade symbol {good}").into())
+}
+
+/// The most units of a good traded at once at the ship's market, only known when the ship is there
+async fn trade_volume(config: &Configuration, ship: &models::Ship, good: models::TradeSymbol, units: i32) -> Result<i32, String> {
+    let mut store = MarketStore::load()?;
+    let market = fetch_market(config, &mut store, &ship.nav.waypoint_symbol).await?;
+
+    Ok(market.trade_goods
+        .iter()
+        .flatten()
+        .find(|trade_good| trade_good.symbol == good)
+        .map_or(units, |trade_good| trade_good.trade_volume))
+}
+
+fn run_script(context: ScriptContext, source: &str) -> Result<(), String> {
+    let mut engine = Engine::new();
+
+    // Sandbox the script, it can only act through the bindings
+    engine
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(100_000)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000)
+        .set_max_modules(0)
+        .set_max_operations(MAX_OPERATIONS)
+        .disable_symbol("eval");
+
+    {
+        let context = context.clone();
+        engine.on_progress(move |_| context.is_stopped().then_some(Dynamic::UNIT));
+    }
+    {
+        let context = context.clone();
+        engine.on_print(move |message| context.log(message));
+    }
+
+    register_ship_bindings(&mut engine, &context);
+    register_market_bindings(&mut engine, &context);
+    register_contract_bindings(&mut engine, &context);
+
+    match engine.run(source) {
+        Ok(()) => Ok(()),
+        // Terminated by the stop signal, the error may be wrapped by the function call it happened in
+        Err(_) if context.is_stopped() => Ok(()),
+        Err(err) => {
+            context.log(&format!("Script error: {err}"));
+            Err(format!("Script error: {err}"))
+        }
+    }
+}
+
+fn register_ship_bindings(engine: &mut Engine, context: &ScriptContext) {
+    let c = context.clone();
+    engine.register_fn("log", move |message: &str| c.log(message));
+
+    let c = context.clone();
+    engine.register_fn("ship", move || to_dynamic(&*c.ship.borrow()));
+
+    let c = context.clone();
+    engine.register_fn("sleep", move |seconds: i64| {
+        c.block_on(async {
+            tokio::time::sleep(Duration::from_secs(seconds.max(0) as u64)).await;
+            Ok(())
+        })
+    });
+
+    let c = context.clone();
+    engine.register_fn("navigate", move |waypoint_symbol: &str| {
+        c.with_ship(async |config, ship| ship::navigate(config, ship, waypoint_symbol).await)
+    });
+
+    let c = context.clone();
+    engine.register_fn("dock", move || c.with_ship(async |config, ship| ship::dock(config, ship).await));
+
+    let c = context.clone();
+    engine.register_fn("orbit", move || c.with_ship(async |config, ship| ship::orbit(config, ship).await));
+
+    let c = context.clone();
+    engine.register_fn("refuel", move || c.with_ship(async |config, ship| ship::refuel(config, ship).await));
+
+    let c = context.clone();
+    engine.register_fn("purchase", move |good: &str, units: i64| {
+        let good = parse_good(good)?;
+        c.with_ship(async |config, ship| {
+            let trade_volume = trade_volume(config, ship, good, units as i32).await?;
+            ship::purchase(config, ship, good, units as i32, trade_volume).await
+        })
+    });
+
+    let c = context.clone();
+    engine.register_fn("sell", move |good: &str, units: i64| {
+        let good = parse_good(good)?;
+        c.with_ship(async |config, ship| {
+            let trade_volume = trade_volume(config, ship, good, units as i32).await?;
+            ship::sell(config, ship, good, units as i32, trade_volume).await
+        })
+    });
+
+    let c = context.clone();
+    engine.register_fn("jettison", move |good: &str, units: i64| {
+        let good = parse_good(good)?;
+        c.with_ship(async |config, ship| ship::jettison(config, ship, good, units as i32).await)
+    });
+
+    // Surveys are shared with the other mining ships
+    let c = context.clone();
+    engine.register_fn("survey", move || {
+        let surveys = c.with_ship(async |config, ship| {
+            ship::wait_for_cooldown(ship).await;
+            let response = retry(|| apis::fleet_api::create_survey(config, &ship.symbol))
+                .await
+                .map_err(|err| format!("Failed to survey: {err}"))?;
+
+            ship.cooldown = response.data.cooldown;
+            surveys::add(response.data.surveys.clone())?;
+
+            Ok(response.data.surveys)
+        })?;
+        to_dynamic(&surveys)
+    });
+
+    engine.register_fn("surveys", |waypoint_symbol: &str| {
+        to_dynamic(&SurveyStore::load()?.at(waypoint_symbol).collect::<Vec<_>>())
+    });
+
+    let c = context.clone();
+    let extract = move |survey: Option<models::Survey>| {
+        let extraction = c.with_ship(async |config, ship| {
+            ship::wait_for_cooldown(ship).await;
+            let response = match &survey {
+                Some(survey) => match retry(|| apis::fleet_api::extract_resources_with_survey(config, &ship.symbol, Some(survey.clone()))).await {
+                    Ok(response) => response,
+                    Err(err) => {
+                        // Like the mining loop, so the other ships don't pick the survey again
+                        if error_code(&err).is_some_and(|code| INVALID_SURVEY_ERROR_CODES.contains(&code)) {
+                            surveys::remove(&survey.signature)?;
+                        }
+                        return Err(format!("Failed to extract resources with survey {}: {err}", survey.signature))
+                    }
+                },
+                None => retry(|| apis::fleet_api::extract_resources(config, &ship.symbol, None))
+                    .await
+                    .map_err(|err| format!("Failed to extract resources: {err}"))?,
+            };
+
+            ship.cargo = response.data.cargo;
+            ship.cooldown = response.data.cooldown;
+            Ok(response.data.extraction)
+        })?;
+        to_dynamic(&extraction)
+    };
+
+    let e = extract.clone();
+    engine.register_fn("extract", move || e(None));
+    engine.register_fn("extract", move |survey: Dynamic| {
+        let survey: models::Survey = rhai::serde::from_dynamic(&survey)?;
+        extract(Some(survey))
+    });
+}
+
+fn register_market_bindings(engine: &mut Engine, context: &ScriptContext) {
+    let c = context.clone();
+    engine.register_fn("market", move |waypoint_symbol: &str| {
+        let market = c.block_on(async {
+            let mut store = MarketStore::load()?;
+            fetch_market(&c.config, &mut store, waypoint_symbol).await
+        })?;
+        to_dynamic(&market)
+    });
+
+    let c = context.clone();
+    engine.register_fn("system", move |system_symbol: &str| {
+        let system = c.block_on(async {
+            retry(|| apis::systems_api::get_system(&c.config, system_symbol))
+                .await
+                .map(|response| response.data)
+                .map_err(|err| format!("Failed to get system {system_symbol}: {err}"))
+        })?;
+        to_dynamic(&system)
+    });
+
+    let c = context.clone();
+    engine.register_fn("marketplaces", move |system_symbol: &str| {
+        let marketplaces = c.block_on(get_marketplaces(&c.config, system_symbol))?;
+        to_dynamic(&marketplaces)
+    });
+
+    let c = context.clone();
+    engine.register_fn("waypoints", move |system_symbol: &str, page: i64| {
+        let waypoints = c.block_on(async {
+            retry(|| apis::systems_api::get_system_waypoints(&c.config, system_symbol, Some(page as i32), Some(20), None, None))
+                .await
+                .map(|response| response.data)
+                .map_err(|err| format!("Failed to get the waypoints of {system_symbol}: {err}"))
+        })?;
+        to_dynamic(&waypoints)
+    });
+}
+
+fn register_contract_bindings(engine: &mut Engine, context: &ScriptContext) {
+    let c = context.clone();
+    engine.register_fn("contracts", move || {
+        let contracts = c.block_on(async {
+            get_contracts(&c.config)
+                .await
+                .map_err(|err| format!("Failed to get contracts: {err}"))
+        })?;
+        to_dynamic(&contracts)
+    });
+
+    let c = context.clone();
+    engine.register_fn("accept_contract", move |contract_id: &str| {
+        let contract = c.block_on(async {
+            retry(|| apis::contracts_api::accept_contract(&c.config, contract_id))
+                .await
+                .map(|response| {
+                    let ship_symbol = c.ship.borrow().symbol.clone();
+                    ledger::record(LedgerEntry::from_contract(&response.data.contract, Some(&ship_symbol), &response.data.agent));
+                    response.data.contract
+                })
+                .map_err(|err| format!("Failed to accept contract {contract_id}: {err}"))
+        })?;
+        to_dynamic(&contract)
+    });
+
+    let c = context.clone();
+    engine.register_fn("deliver_contract", move |contract_id: &str, good: &str, units: i64| {
+        let request = models::DeliverContractRequest::new(c.ship.borrow().symbol.clone(), good.to_string(), units as i32);
+        let contract = c.with_ship(async |config, ship| {
+            let response = retry(|| apis::contracts_api::deliver_contract(config, contract_id, Some(request.clone())))
+                .await
+                .map_err(|err| format!("Failed to deliver {units} {good}: {err}"))?;
+
+            ship.cargo = response.data.cargo;
+            Ok(response.data.contract)
+        })?;
+        to_dynamic(&contract)
+    });
+
+    let c = context.clone();
+    engine.register_fn("fulfill_contract", move |contract_id: &str| {
+        let contract = c.block_on(async {
+            retry(|| apis::contracts_api::fulfill_contract(&c.config, contract_id))
+                .await
+                .map(|response| {
+                    let ship_symbol = c.ship.borrow().symbol.clone();
+                    ledger::record(LedgerEntry::from_contract(&response.data.contract, Some(&ship_symbol), &response.data.agent));
+                    response.data.contract
+                })
+                .map_err(|err| format!("Failed to fulfill contract {contract_id}: {err}"))
+        })?;
+        to_dynamic(&contract)
+    });
+}
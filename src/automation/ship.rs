@@ -0,0 +1,192 @@
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models::{self, ShipNavStatus};
+
+use super::events::{emit, Event};
+use super::{log, retry, sleep_until};
+use crate::store::ledger::{self, LedgerEntry};
+
+/// Wait for the ship to reach its destination if it's in transit
+pub async fn wait_for_arrival(ship: &mut models::Ship) {
+    if ship.nav.status != ShipNavStatus::InTransit {
+        return
+    }
+
+    log(&ship.symbol, format!("Waiting to arrive at {}", ship.nav.route.destination.symbol));
+    sleep_until(&ship.nav.route.arrival).await;
+    ship.nav.status = ShipNavStatus::InOrbit;
+
+    emit(Event::Arrival {
+        ship_symbol: ship.symbol.clone(),
+        nav: (*ship.nav).clone(),
+    });
+}
+
+/// Wait for the ship's reactor cooldown to expire
+pub async fn wait_for_cooldown(ship: &mut models::Ship) {
+    if let Some(expiration) = &ship.cooldown.expiration {
+        sleep_until(expiration).await;
+
+        emit(Event::CooldownCompleted {
+            ship_symbol: ship.symbol.clone(),
+            cooldown: (*ship.cooldown).clone(),
+        });
+        ship.cooldown.expiration = None;
+        ship.cooldown.remaining_seconds = 0;
+    }
+}
+
+pub async fn orbit(config: &Configuration, ship: &mut models::Ship) -> Result<(), String> {
+    if ship.nav.status == ShipNavStatus::InOrbit {
+        return Ok(())
+    }
+
+    let response = retry(|| apis::fleet_api::orbit_ship(config, &ship.symbol))
+        .await
+        .map_err(|err| format!("Failed to orbit {}: {err}", ship.symbol))?;
+
+    ship.nav = response.data.nav;
+    Ok(())
+}
+
+pub async fn dock(config: &Configuration, ship: &mut models::Ship) -> Result<(), String> {
+    if ship.nav.status == ShipNavStatus::Docked {
+        return Ok(())
+    }
+
+    let response = retry(|| apis::fleet_api::dock_ship(config, &ship.symbol))
+        .await
+        .map_err(|err| format!("Failed to dock {}: {err}", ship.symbol))?;
+
+    ship.nav = response.data.nav;
+    Ok(())
+}
+
+/// Navigate to a waypoint of the current system and wait for the arrival
+pub async fn navigate(config: &Configuration, ship: &mut models::Ship, waypoint_symbol: &str) -> Result<(), String> {
+    wait_for_arrival(ship).await;
+    if ship.nav.waypoint_symbol == waypoint_symbol {
+        return Ok(())
+    }
+
+    orbit(config, ship).await?;
+
+    let request = models::NavigateShipRequest::new(waypoint_symbol.to_string());
+    let response = retry(|| apis::fleet_api::navigate_ship(config, &ship.symbol, Some(request.clone())))
+        .await
+        .map_err(|err| format!("Failed to navigate {} to {waypoint_symbol}: {err}", ship.symbol))?;
+
+    ship.nav = response.data.nav;
+    ship.fuel = response.data.fuel;
+
+    log(&ship.symbol, format!("Navigating to {waypoint_symbol}, arrival at {}", ship.nav.route.arrival));
+    wait_for_arrival(ship).await;
+    Ok(())
+}
+
+/// Refuel the ship at the current waypoint.
+/// Not every market sells fuel so failing to refuel is only logged.
+pub async fn refuel(config: &Configuration, ship: &mut models::Ship) -> Result<(), String> {
+    if ship.fuel.capacity == 0 || ship.fuel.current == ship.fuel.capacity {
+        return Ok(())
+    }
+
+    dock(config, ship).await?;
+
+    let request = models::RefuelShipRequest::new();
+    match retry(|| apis::fleet_api::refuel_ship(config, &ship.symbol, Some(request.clone()))).await {
+        Ok(response) => {
+            log(&ship.symbol, format!("Refueled for {} credits", response.data.transaction.total_price));
+            ship.fuel = response.data.fuel;
+            ledger::record(LedgerEntry::from_market(&response.data.transaction, Some(&response.data.agent)));
+        }
+        Err(err) => log(&ship.symbol, format!("Failed to refuel: {err}")),
+    }
+
+    Ok(())
+}
+
+/// Purchase goods at the current market, in batches of the market's trade volume
+pub async fn purchase(
+    config: &Configuration,
+    ship: &mut models::Ship,
+    good: models::TradeSymbol,
+    units: i32,
+    trade_volume: i32,
+) -> Result<(), String> {
+    dock(config, ship).await?;
+
+    let mut remaining = units;
+    while remaining > 0 {
+        let batch = remaining.min(trade_volume.max(1));
+        let request = models::PurchaseCargoRequest::new(good, batch);
+        let response = retry(|| apis::fleet_api::purchase_cargo(config, &ship.symbol, Some(request.clone())))
+            .await
+            .map_err(|err| format!("Failed to purchase {batch} {good}: {err}"))?;
+
+        log(&ship.symbol, format!(
+            "Purchased {batch} {good} for {} credits ({} credits left)",
+            response.data.transaction.total_price, response.data.agent.credits
+        ));
+        ship.cargo = response.data.cargo;
+        ledger::record(LedgerEntry::from_market(&response.data.transaction, Some(&response.data.agent)));
+        remaining -= batch;
+    }
+
+    Ok(())
+}
+
+/// Sell goods at the current market, in batches of the market's trade volume
+pub async fn sell(
+    config: &Configuration,
+    ship: &mut models::Ship,
+    good: models::TradeSymbol,
+    units: i32,
+    trade_volume: i32,
+) -> Result<(), String> {
+    dock(config, ship).await?;
+
+    let mut remaining = units;
+    while remaining > 0 {
+        let batch = remaining.min(trade_volume.max(1));
+        let request = models::SellCargoRequest::new(good, batch);
+        let response = retry(|| apis::fleet_api::sell_cargo(config, &ship.symbol, Some(request.clone())))
+            .await
+            .map_err(|err| format!("Failed to sell {batch} {good}: {err}"))?;
+
+        log(&ship.symbol, format!(
+            "Sold {batch} {good} for {} credits ({} credits left)",
+            response.data.transaction.total_price, response.data.agent.credits
+        ));
+        ship.cargo = response.data.cargo;
+        ledger::record(LedgerEntry::from_market(&response.data.transaction, Some(&response.data.agent)));
+        remaining -= batch;
+    }
+
+    Ok(())
+}
+
+pub async fn jettison(config: &Configuration, ship: &mut models::Ship, good: models::TradeSymbol, units: i32) -> Result<(), String> {
+    let request = models::JettisonRequest::new(good, units);
+    let response = retry(|| apis::fleet_api::jettison(config, &ship.symbol, Some(request.clone())))
+        .await
+        .map_err(|err| format!("Failed to jettison {units} {good}: {err}"))?;
+
+    log(&ship.symbol, format!("Jettisoned {units} {good}"));
+    ship.cargo = response.data.cargo;
+    Ok(())
+}
+
+/// The lowest condition of the ship's frame, reactor and engine
+pub fn condition(ship: &models::Ship) -> f64 {
+    ship.frame.condition
+        .min(ship.reactor.condition)
+        .min(ship.engine.condition)
+}
+
+/// The number of units of a good in the ship's cargo
+pub fn units_in_cargo(ship: &models::Ship, good: models::TradeSymbol) -> i32 {
+    ship.cargo.inventory
+        .iter()
+        .find(|item| item.symbol == good)
+        .map_or(0, |item| item.units)
+}
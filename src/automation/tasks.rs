@@ -0,0 +1,190 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use space_trader_api::apis::configuration::Configuration;
+use space_trader_api::models;
+
+use super::explorer::ExplorationOptions;
+use super::mining::MiningOptions;
+use super::probes::ProbeOptions;
+use super::{construction, contract, explorer, maintenance, mining, probes, script, ship};
+use crate::cli::flag_value;
+
+const TASKS_FILE: &str = "./tasks.json";
+
+/// Prevents the ships from overwriting each other's changes to the tasks file
+static TASKS_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "task", rename_all = "kebab-case")]
+pub enum Task {
+    Contract { contract_id: String },
+    Mine(MiningOptions),
+    Explore(ExplorationOptions),
+    /// Keep the markets' prices fresh with a probe, queued by the `probes` command
+    Probe(ProbeOptions),
+    Navigate { waypoint_symbol: String },
+    /// Haul materials to a construction site until it's complete
+    Construction { waypoint_symbol: String },
+    /// Repair the ship at the nearest shipyard
+    Repair,
+    /// Run a Rhai script from the `scripts` directory
+    Script { path: String },
+}
+
+impl Task {
+    /// Parse the `--task` argument and the arguments of the task
+    pub fn from_args(args: &[String]) -> Result<Task, String> {
+        match flag_value(args, "--task=") {
+            Some("contract") => Ok(Task::Contract {
+                contract_id: flag_value(args, "--contract=").ok_or("No contract argument")?.to_string(),
+            }),
+            Some("mine") => Ok(Task::Mine(MiningOptions::from_args(args)?)),
+            Some("explore") => Ok(Task::Explore(ExplorationOptions::from_args(args)?)),
+            Some("navigate") => Ok(Task::Navigate {
+                waypoint_symbol: flag_value(args, "--waypoint=").ok_or("No waypoint argument")?.to_string(),
+            }),
+            Some("construction") => Ok(Task::Construction {
+                waypoint_symbol: flag_value(args, "--waypoint=").ok_or("No waypoint argument")?.to_string(),
+            }),
+            Some("repair") => Ok(Task::Repair),
+            Some("script") => Ok(Task::Script {
+                path: flag_value(args, "--script=").ok_or("No script argument")?.to_string(),
+            }),
+            Some(task) => Err(format!("Unknown task {task}")),
+            None => Err(String::from("No task argument")),
+        }
+    }
+
+    pub async fn run(&self, config: &Configuration, mut ship: models::Ship) -> Result<(), String> {
+        match self {
+            Task::Contract { contract_id } => contract::run(config, contract_id, &ship.symbol, false).await,
+            Task::Mine(options) => mining::run(config, ship, options).await,
+            Task::Explore(options) => explorer::run(config, ship, options).await,
+            Task::Probe(options) => probes::run(config, ship, options).await,
+            Task::Navigate { waypoint_symbol } => ship::navigate(config, &mut ship, waypoint_symbol).await,
+            Task::Construction { waypoint_symbol } => construction::run(config, ship, waypoint_symbol).await,
+            Task::Repair => maintenance::repair(config, ship).await,
+            Task::Script { path } => script::run(config, ship, path).await,
+        }
+    }
+}
+
+impl std::fmt::Display for Task {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Task::Contract { contract_id } => write!(f, "contract {contract_id}"),
+            Task::Mine(options) => match &options.waypoint_symbol {
+                Some(waypoint_symbol) => write!(f, "mine at {waypoint_symbol}"),
+                None => write!(f, "mine"),
+            },
+            Task::Explore(options) => match &options.center {
+                Some(center) => write!(f, "explore around {center}"),
+                None => write!(f, "explore"),
+            },
+            Task::Probe(options) => write!(f, "probe {} markets", options.markets.len()),
+            Task::Navigate { waypoint_symbol } => write!(f, "navigate to {waypoint_symbol}"),
+            Task::Construction { waypoint_symbol } => write!(f, "supply construction at {waypoint_symbol}"),
+            Task::Repair => write!(f, "repair"),
+            Task::Script { path } => write!(f, "script {path}"),
+        }
+    }
+}
+
+/// A task removed from the queue after failing too many times in a row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedTask {
+    pub task: Task,
+    pub error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+/// The task queue of every ship.
+/// The first task of a queue is the one running, it's removed once completed
+/// so an interrupted task is resumed on restart.
+#[derive(Default, Serialize, Deserialize)]
+pub struct TaskQueues {
+    ships: HashMap<String, VecDeque<Task>>,
+    /// The tasks given up on, by ship symbol
+    #[serde(default)]
+    failed: HashMap<String, Vec<FailedTask>>,
+}
+
+impl TaskQueues {
+    pub fn load() -> Result<TaskQueues, String> {
+        crate::store::load(TASKS_FILE)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        crate::store::save(TASKS_FILE, self)
+    }
+
+    pub fn current(&self, ship_symbol: &str) -> Option<&Task> {
+        self.ships.get(ship_symbol)?.front()
+    }
+}
+
+/// Add a task at the end of a ship's queue
+pub fn push(ship_symbol: &str, task: Task) -> Result<(), String> {
+    let _lock = TASKS_FILE_LOCK.lock().unwrap();
+
+    let mut queues = TaskQueues::load()?;
+    queues.ships.entry(ship_symbol.to_string()).or_default().push_back(task);
+    queues.save()
+}
+
+/// Add a task at the front of a ship's queue, to run it before the current one
+pub fn push_front(ship_symbol: &str, task: Task) -> Result<(), String> {
+    let _lock = TASKS_FILE_LOCK.lock().unwrap();
+
+    let mut queues = TaskQueues::load()?;
+    queues.ships.entry(ship_symbol.to_string()).or_default().push_front(task);
+    queues.save()
+}
+
+/// Remove the current task of a ship once it's completed
+pub fn complete(ship_symbol: &str, task: &Task) -> Result<(), String> {
+    let _lock = TASKS_FILE_LOCK.lock().unwrap();
+
+    let mut queues = TaskQueues::load()?;
+    let Some(queue) = queues.ships.get_mut(ship_symbol) else {
+        return Ok(())
+    };
+
+    // The queue may have been edited while the task was running
+    if queue.front() == Some(task) {
+        queue.pop_front();
+    }
+    queues.save()
+}
+
+/// Move the current task of a ship to its failed tasks, so the next task can run
+pub fn fail(ship_symbol: &str, task: &Task, error: String) -> Result<(), String> {
+    let _lock = TASKS_FILE_LOCK.lock().unwrap();
+
+    let mut queues = TaskQueues::load()?;
+    let Some(queue) = queues.ships.get_mut(ship_symbol) else {
+        return Ok(())
+    };
+
+    if queue.front() == Some(task) {
+        queue.pop_front();
+        queues.failed.entry(ship_symbol.to_string()).or_default().push(FailedTask {
+            task: task.clone(),
+            error,
+            failed_at: Utc::now(),
+        });
+    }
+    queues.save()
+}
+
+/// `space-trader assign --ship=<symbol> --task=<contract|mine|navigate|script> [task arguments]`
+pub fn assign_command(args: &[String]) -> Result<(), String> {
+    let ship_symbol = flag_value(args, "--ship=").ok_or("No ship argument")?;
+    let task = Task::from_args(args)?;
+
+    println!("Assigned {task} to {ship_symbol}");
+    push(ship_symbol, task)
+}
@@ -0,0 +1,18 @@
+pub const TOKEN_FLAG: &str = "--token=";
+
+/// Get the value of a `--flag=value` argument
+pub fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args
+        .iter()
+        .find(|arg| arg.starts_with(flag))
+        .map(|arg| &arg[flag.len()..])
+}
+
+/// Check if a `--flag` argument is present
+pub fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|arg| arg == flag)
+}
+
+pub fn get_token(args: &[String]) -> Option<String> {
+    flag_value(args, TOKEN_FLAG).map(str::to_string)
+}
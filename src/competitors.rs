@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use chrono::TimeDelta;
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+
+use crate::automation::retry;
+use crate::cli::{flag_value, has_flag};
+use crate::store::agents::{self, AgentHistory, AgentStore};
+
+const DEFAULT_TRACKING_INTERVAL: Duration = Duration::from_secs(3600);
+/// The period over which the growth of the agents is compared
+pub const GROWTH_PERIOD: TimeDelta = TimeDelta::hours(24);
+/// The number of agents printed by the command
+const RANKING_LENGTH: usize = 20;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Ranking {
+    #[default]
+    Credits,
+    Ships,
+    /// The credits earned over the growth period
+    Growth,
+}
+
+impl Ranking {
+    pub const ALL: [Ranking; 3] = [Ranking::Credits, Ranking::Ships, Ranking::Growth];
+
+    /// Parse the `--by` argument
+    pub fn parse(ranking: &str) -> Option<Ranking> {
+        Ranking::ALL.into_iter().find(|by| by.to_string().eq_ignore_ascii_case(ranking))
+    }
+
+    /// The value the agents are ranked by
+    pub fn value(&self, agent: &AgentHistory) -> i64 {
+        match self {
+            Ranking::Credits => agent.latest().map_or(0, |sample| sample.credits),
+            Ranking::Ships => agent.latest().map_or(0, |sample| sample.ship_count as i64),
+            Ranking::Growth => agent.credits_growth(GROWTH_PERIOD).unwrap_or_default(),
+        }
+    }
+}
+
+impl std::fmt::Display for Ranking {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// Get every agent of the server, page by page
+pub async fn fetch_agents() -> Result<Vec<models::Agent>, String> {
+    let config = Configuration::new();
+    let mut agents = Vec::new();
+    let mut page = 1;
+
+    loop {
+        let mut response = retry(|| apis::agents_api::get_agents(&config, Some(page), Some(20)))
+            .await
+            .map_err(|err| format!("Failed to get the agents: {err}"))?;
+
+        agents.append(&mut response.data);
+        if response.meta.page * response.meta.limit >= response.meta.total {
+            return Ok(agents)
+        }
+        page += 1;
+    }
+}
+
+/// Add a sample of every agent to the store, returning the number of agents
+pub async fn track() -> Result<usize, String> {
+    let agents = fetch_agents().await?;
+    let count = agents.len();
+    agents::record(agents)?;
+
+    Ok(count)
+}
+
+/// `space-trader competitors [--by=credits|ships|growth] [--watch] [--interval=3600]`
+///
+/// Sample every agent and print the ranking, sampling them again every interval with `--watch`
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let ranking = match flag_value(args, "--by=") {
+        Some(by) => Ranking::parse(by).ok_or(format!("Invalid ranking {by}"))?,
+        None => Ranking::Credits,
+    };
+    let interval = match flag_value(args, "--interval=") {
+        Some(interval) => Duration::from_secs(interval.parse().map_err(|_| format!("Invalid interval {interval}"))?),
+        None => DEFAULT_TRACKING_INTERVAL,
+    };
+
+    loop {
+        let count = track().await?;
+        let store = AgentStore::load()?;
+
+        println!("{count} agents, ranked by {}", ranking.to_string().to_lowercase());
+        println!("{:>4} {:<20} {:<12} {:>15} {:>6} {:>15}", "", "agent", "headquarters", "credits", "ships", "24h growth");
+        for (rank, agent) in store.ranking(|agent| ranking.value(agent)).into_iter().take(RANKING_LENGTH).enumerate() {
+            let Some(sample) = agent.latest() else {
+                continue
+            };
+            println!(
+                "{:>3}. {:<20} {:<12} {:>15} {:>6} {:>15}",
+                rank + 1, agent.symbol, agent.headquarters, sample.credits, sample.ship_count,
+                agent.credits_growth(GROWTH_PERIOD).unwrap_or_default()
+            );
+        }
+
+        if !has_flag(args, "--watch") {
+            return Ok(())
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
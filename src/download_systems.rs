@@ -1,7 +1,9 @@
 use space_trader_api::apis as apis;
 use space_trader_api::apis::configuration::Configuration;
 
-const TOKEN_FLAG: &str = "--token=";
+use crate::automation::retry;
+use crate::cli::{has_flag, TOKEN_FLAG};
+use crate::store::galaxy::SYSTEMS_FILE;
 
 pub async fn download_systems(token: &str) -> Result<(), String> {
     let config = Configuration::from_bearer_access_token(token.to_string());
@@ -11,18 +13,29 @@ pub async fn download_systems(token: &str) -> Result<(), String> {
 
     println!("{:?}", agent.data);
 
-    let systems = apis::systems_api::get_systems(&config)
-        .await
-        .map_err(|err| format!("Failed to get systems: {err}"))?;
+    let mut systems = Vec::new();
+    let mut page = 1;
+    loop {
+        let mut response = retry(|| apis::systems_api::get_systems(&config, Some(page), Some(20)))
+            .await
+            .map_err(|err| format!("Failed to get systems: {err}"))?;
+
+        systems.append(&mut response.data);
+        println!("Pulled {} of {} systems", systems.len(), response.meta.total);
+        if response.meta.page * response.meta.limit >= response.meta.total {
+            break
+        }
+        page += 1;
+    }
 
     let systems_json = serde_json::to_string(&systems).unwrap();
-    std::fs::write("./systems.json", systems_json).unwrap();
+    std::fs::write(SYSTEMS_FILE, systems_json).unwrap();
 
     Ok(())
 }
 
-pub async fn download_systems_if_needed(args: Vec<String>) -> Result<(), String> {
-    if args.iter().find(|&arg| arg == "--download-systems").is_some() { // Download if specified
+pub async fn download_systems_if_needed(args: &[String]) -> Result<(), String> {
+    if has_flag(args, "--download-systems") { // Download if specified
         let token_arg = args
             .iter().find(|&arg| arg.starts_with(TOKEN_FLAG))
             .expect("No token argument");
@@ -31,7 +44,7 @@ pub async fn download_systems_if_needed(args: Vec<String>) -> Result<(), String>
         download_systems(token).await
 
     } else {
-        if std::fs::exists("./systems.json").unwrap() { // Download if needed (when systems.json doesn't exist)
+        if std::fs::exists(SYSTEMS_FILE).unwrap() { // Download if needed (when systems.json doesn't exist)
             return Ok(())
         };
 
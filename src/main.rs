@@ -1,12 +1,46 @@
 mod ui;
+mod cli;
+mod time;
+mod store;
+mod symbols;
+mod automation;
 mod download_systems;
+mod report;
+mod competitors;
+mod status;
 
 use download_systems::download_systems_if_needed;
 
 #[tokio::main]
 async fn main() -> iced::Result {
     let args = std::env::args().collect::<Vec<_>>();
-    download_systems_if_needed(args).await.unwrap();
 
-    ui::run()
-}
\ No newline at end of file
+    let command_result = match args.get(1).map(String::as_str) {
+        Some("run-contract") => automation::contract::run_command(&args).await,
+        Some("construction") => automation::construction::run_command(&args).await,
+        Some("mine") => automation::mining::run_command(&args).await,
+        Some("script") => automation::script::run_command(&args).await,
+        Some("explore") => automation::explorer::run_command(&args).await,
+        Some("crawl") => automation::crawler::run_command(&args).await,
+        Some("probes") => automation::probes::run_command(&args).await,
+        Some("scan") => automation::scanner::run_command(&args).await,
+        Some("maintenance") => automation::maintenance::run_command(&args).await,
+        Some("daemon") => automation::daemon::run_command(&args).await,
+        Some("ledger") => store::ledger::run_command(&args).await,
+        Some("report") => report::run_command(&args),
+        Some("competitors") => competitors::run_command(&args).await,
+        Some("status") => status::run_command().await,
+        Some("assign") => automation::tasks::assign_command(&args),
+        _ => {
+            download_systems_if_needed(&args).await.unwrap();
+            return ui::run(cli::get_token(&args))
+        }
+    };
+
+    if let Err(err) = command_result {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
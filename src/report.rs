@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, DurationRound, NaiveDate, TimeDelta, Utc};
+use serde::Serialize;
+
+use crate::cli::flag_value;
+use crate::store::ledger::{EntryKind, Ledger, LedgerEntry};
+
+/// The strategy of the transactions made outside of the automation
+const MANUAL_STRATEGY: &str = "manual";
+
+/// The revenue and costs of a group of transactions, costs are positive
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Breakdown {
+    pub revenue: i64,
+    pub fuel: i64,
+    pub repairs: i64,
+    pub ships: i64,
+    pub mounts: i64,
+    /// The goods purchased to be traded or delivered
+    pub goods: i64,
+}
+
+impl Breakdown {
+    pub fn add(&mut self, entry: &LedgerEntry) {
+        let cost = -entry.amount;
+        match entry.kind {
+            EntryKind::MarketSale | EntryKind::ShipScrap | EntryKind::ContractAccepted | EntryKind::ContractFulfilled => {
+                self.revenue += entry.amount
+            }
+            EntryKind::MarketPurchase if entry.good.as_deref() == Some("FUEL") => self.fuel += cost,
+            EntryKind::MarketPurchase => self.goods += cost,
+            EntryKind::Repair => self.repairs += cost,
+            EntryKind::ShipPurchase => self.ships += cost,
+            EntryKind::ShipModification => self.mounts += cost,
+        }
+    }
+
+    pub fn costs(&self) -> i64 {
+        self.fuel + self.repairs + self.ships + self.mounts + self.goods
+    }
+
+    pub fn net(&self) -> i64 {
+        self.revenue - self.costs()
+    }
+}
+
+/// The net profit made during an hour
+#[derive(Debug, Clone, Serialize)]
+pub struct HourlyProfit {
+    pub hour: DateTime<Utc>,
+    pub net: i64,
+}
+
+/// The profit and loss over a date range
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Report {
+    /// The first day of the range, inclusive
+    pub from: Option<NaiveDate>,
+    /// The last day of the range, inclusive
+    pub to: Option<NaiveDate>,
+    pub total: Breakdown,
+    pub ships: BTreeMap<String, Breakdown>,
+    pub strategies: BTreeMap<String, Breakdown>,
+    /// The average net profit per hour, from the first to the last transaction
+    pub credits_per_hour: f64,
+    /// Every hour from the first to the last transaction
+    pub hourly: Vec<HourlyProfit>,
+}
+
+impl Report {
+    pub fn new(ledger: &Ledger, from: Option<NaiveDate>, to: Option<NaiveDate>) -> Report {
+        let entries: Vec<&LedgerEntry> = ledger.entries
+            .iter()
+            .filter(|entry| {
+                let day = entry.timestamp.date_naive();
+                from.is_none_or(|from| day >= from) && to.is_none_or(|to| day <= to)
+            })
+            .collect();
+
+        let mut report = Report { from, to, ..Report::default() };
+        let mut hourly: BTreeMap<DateTime<Utc>, i64> = BTreeMap::new();
+
+        for entry in &entries {
+            report.total.add(entry);
+            if let Some(ship_symbol) = &entry.ship_symbol {
+                report.ships.entry(ship_symbol.clone()).or_default().add(entry);
+            }
+            report.strategies
+                .entry(entry.strategy.clone().unwrap_or(String::from(MANUAL_STRATEGY)))
+                .or_default()
+                .add(entry);
+            *hourly.entry(hour_of(entry.timestamp)).or_default() += entry.amount;
+        }
+
+        // Hours without transactions are kept to show the idle time
+        if let (Some(first), Some(last)) = (hourly.keys().next().copied(), hourly.keys().last().copied()) {
+            let hours = (last - first).num_hours() + 1;
+            report.credits_per_hour = report.total.net() as f64 / hours as f64;
+            report.hourly = (0..hours)
+                .map(|hour| first + TimeDelta::hours(hour))
+                .map(|hour| HourlyProfit { hour, net: hourly.get(&hour).copied().unwrap_or_default() })
+                .collect();
+        }
+
+        report
+    }
+
+    /// One row per ship, strategy and hour, with the total first
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("group,key,revenue,fuel,repairs,ships,mounts,goods,net\n");
+        let mut push_row = |group: &str, key: &str, breakdown: &Breakdown| {
+            csv.push_str(&format!(
+                "{group},{key},{},{},{},{},{},{},{}\n",
+                breakdown.revenue, breakdown.fuel, breakdown.repairs, breakdown.ships,
+                breakdown.mounts, breakdown.goods, breakdown.net()
+            ));
+        };
+
+        push_row("total", "", &self.total);
+        for (ship_symbol, breakdown) in &self.ships {
+            push_row("ship", ship_symbol, breakdown);
+        }
+        for (strategy, breakdown) in &self.strategies {
+            push_row("strategy", strategy, breakdown);
+        }
+        for hourly in &self.hourly {
+            csv.push_str(&format!("hour,{},,,,,,,{}\n", hourly.hour.to_rfc3339(), hourly.net));
+        }
+
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|err| format!("Failed to serialize the report: {err}"))
+    }
+
+    /// Write the report to a `.csv` or `.json` file
+    pub fn export(&self, path: &str) -> Result<(), String> {
+        let content = match path.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("csv") => self.to_csv(),
+            Some("json") => self.to_json()?,
+            _ => return Err(format!("Unknown export format for {path}, expected a .csv or .json file")),
+        };
+
+        std::fs::write(path, content).map_err(|err| format!("Failed to write {path}: {err}"))
+    }
+}
+
+fn hour_of(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    timestamp.duration_trunc(TimeDelta::hours(1)).unwrap_or(timestamp)
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| format!("Invalid date {date}, expected YYYY-MM-DD"))
+}
+
+/// `space-trader report [--from=2024-01-01] [--to=2024-01-31] [--export=report.csv|report.json]`
+pub fn run_command(args: &[String]) -> Result<(), String> {
+    let from = flag_value(args, "--from=").map(parse_date).transpose()?;
+    let to = flag_value(args, "--to=").map(parse_date).transpose()?;
+    let report = Report::new(&Ledger::load(), from, to);
+
+    if let Some(path) = flag_value(args, "--export=") {
+        report.export(path)?;
+        println!("Exported the report to {path}");
+        return Ok(())
+    }
+
+    println!("{:<24} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}", "", "revenue", "fuel", "repairs", "ships", "mounts", "goods", "net");
+    let print_row = |key: &str, breakdown: &Breakdown| println!(
+        "{key:<24} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        breakdown.revenue, breakdown.fuel, breakdown.repairs, breakdown.ships,
+        breakdown.mounts, breakdown.goods, breakdown.net()
+    );
+
+    print_row("Total", &report.total);
+    println!("\nShips");
+    for (ship_symbol, breakdown) in &report.ships {
+        print_row(ship_symbol, breakdown);
+    }
+    println!("\nStrategies");
+    for (strategy, breakdown) in &report.strategies {
+        print_row(strategy, breakdown);
+    }
+
+    println!("\n{:.0} credits per hour", report.credits_per_hour);
+    for hourly in &report.hourly {
+        println!("{} {:>10}", hourly.hour.format("%Y-%m-%d %H:00"), hourly.net);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: EntryKind, ship_symbol: &str, good: Option<&str>, amount: i64, strategy: Option<&str>, timestamp: &str) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: timestamp.parse().unwrap(),
+            kind,
+            ship_symbol: Some(ship_symbol.to_string()),
+            waypoint_symbol: None,
+            good: good.map(str::to_string),
+            contract_id: None,
+            units: 1,
+            price_per_unit: amount.abs() as i32,
+            amount,
+            credits_after: None,
+            strategy: strategy.map(str::to_string),
+        }
+    }
+
+    fn ledger() -> Ledger {
+        Ledger {
+            entries: vec![
+                entry(EntryKind::MarketPurchase, "SHIP-1", Some("FUEL"), -100, Some("mine"), "2024-03-01T10:15:00Z"),
+                entry(EntryKind::MarketSale, "SHIP-1", Some("IRON_ORE"), 1000, Some("mine"), "2024-03-01T10:45:00Z"),
+                entry(EntryKind::MarketPurchase, "SHIP-2", Some("COPPER"), -400, Some("contract"), "2024-03-01T12:00:00Z"),
+                entry(EntryKind::ContractFulfilled, "SHIP-2", None, 2000, Some("contract"), "2024-03-01T12:30:00Z"),
+                entry(EntryKind::Repair, "SHIP-1", None, -300, None, "2024-03-02T09:00:00Z"),
+                entry(EntryKind::ShipModification, "SHIP-1", Some("MOUNT_MINING_LASER_I"), -200, None, "2024-03-02T09:10:00Z"),
+            ],
+        }
+    }
+
+    #[test]
+    fn costs_are_split_by_good() {
+        let report = Report::new(&ledger(), None, None);
+
+        assert_eq!(report.total.revenue, 3000);
+        assert_eq!(report.total.fuel, 100);
+        assert_eq!(report.total.goods, 400);
+        assert_eq!(report.total.repairs, 300);
+        assert_eq!(report.total.mounts, 200);
+        assert_eq!(report.total.costs(), 1000);
+        assert_eq!(report.total.net(), 2000);
+    }
+
+    #[test]
+    fn aggregated_per_ship() {
+        let report = Report::new(&ledger(), None, None);
+
+        assert_eq!(report.ships.len(), 2);
+        assert_eq!(report.ships["SHIP-1"].revenue, 1000);
+        assert_eq!(report.ships["SHIP-1"].costs(), 600);
+        assert_eq!(report.ships["SHIP-2"].net(), 1600);
+    }
+
+    #[test]
+    fn aggregated_per_strategy() {
+        let report = Report::new(&ledger(), None, None);
+
+        assert_eq!(report.strategies.keys().collect::<Vec<_>>(), ["contract", MANUAL_STRATEGY, "mine"]);
+        assert_eq!(report.strategies["mine"].net(), 900);
+        assert_eq!(report.strategies["contract"].net(), 1600);
+        // The transactions without a strategy were made by hand
+        assert_eq!(report.strategies[MANUAL_STRATEGY].costs(), 500);
+    }
+
+    #[test]
+    fn filtered_by_date() {
+        let day = parse_date("2024-03-02").unwrap();
+        let report = Report::new(&ledger(), Some(day), Some(day));
+
+        assert_eq!(report.total.revenue, 0);
+        assert_eq!(report.total.costs(), 500);
+        assert!(!report.ships.contains_key("SHIP-2"));
+    }
+
+    #[test]
+    fn hourly_profit_includes_idle_hours() {
+        let day = parse_date("2024-03-01").unwrap();
+        let report = Report::new(&ledger(), Some(day), Some(day));
+
+        let hourly: Vec<i64> = report.hourly.iter().map(|hourly| hourly.net).collect();
+        assert_eq!(hourly, [900, 0, 1600]);
+        assert_eq!(report.credits_per_hour, 2500. / 3.);
+    }
+}
@@ -0,0 +1,92 @@
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+
+use crate::store::{self, RESET_DATE_FILE, UNIVERSE_FILES};
+use crate::time;
+
+/// The status of the server, its leaderboards and announcements
+pub async fn fetch() -> Result<models::GetStatus200Response, String> {
+    apis::default_api::get_status(&Configuration::new())
+        .await
+        .map_err(|err| format!("Failed to get the server status: {err}"))
+}
+
+/// The cached files built for an earlier reset, which describe a universe that no longer exists
+pub fn stale_files(status: &models::GetStatus200Response) -> Result<Vec<&'static str>, String> {
+    let cached_files = UNIVERSE_FILES.into_iter().filter(|path| std::path::Path::new(path).exists());
+
+    let stale_files = match store::load::<Option<String>>(RESET_DATE_FILE)? {
+        Some(cache_reset_date) if cache_reset_date == status.reset_date => Vec::new(),
+        Some(_) => cached_files.collect(),
+        // The caches from before the reset date was recorded can only be dated by their files
+        None => {
+            let Ok(reset_date) = NaiveDate::parse_from_str(&status.reset_date, "%Y-%m-%d") else {
+                return Ok(Vec::new())
+            };
+            let reset = Utc.from_utc_datetime(&reset_date.and_time(NaiveTime::MIN));
+            cached_files
+                .filter(|path| store::modified_at(path).is_some_and(|modified| modified < reset))
+                .collect()
+        }
+    };
+
+    Ok(stale_files)
+}
+
+/// A warning listing the stale files, if any.
+/// Without stale files, the cache is recorded as built for the current reset.
+pub fn stale_files_warning(status: &models::GetStatus200Response) -> Option<String> {
+    let stale_files = match stale_files(status) {
+        Ok(stale_files) => stale_files,
+        Err(err) => return Some(err),
+    };
+    if stale_files.is_empty() {
+        if let Err(err) = store::save(RESET_DATE_FILE, &status.reset_date) {
+            return Some(err)
+        }
+    }
+
+    (!stale_files.is_empty()).then(|| format!(
+        "The server was reset on {}, delete the outdated {}",
+        status.reset_date, stale_files.join(", ")
+    ))
+}
+
+/// `space-trader status`
+pub async fn run_command() -> Result<(), String> {
+    let status = fetch().await?;
+
+    println!("{} ({})", status.status, status.version);
+    println!("Reset on {}, next reset in {} (every {})",
+        status.reset_date, time::format_countdown(&status.server_resets.next), status.server_resets.frequency
+    );
+    println!("{} agents, {} ships, {} systems, {} waypoints",
+        status.stats.agents, status.stats.ships, status.stats.systems, status.stats.waypoints
+    );
+
+    println!("\nMost credits");
+    for (rank, entry) in status.leaderboards.most_credits.iter().enumerate() {
+        println!("{:>3}. {:<20} {:>15}", rank + 1, entry.agent_symbol, entry.credits);
+    }
+    println!("\nMost submitted charts");
+    for (rank, entry) in status.leaderboards.most_submitted_charts.iter().enumerate() {
+        println!("{:>3}. {:<20} {:>15}", rank + 1, entry.agent_symbol, entry.chart_count);
+    }
+
+    for announcement in &status.announcements {
+        println!("\n{}\n{}", announcement.title, announcement.body);
+    }
+    if !status.links.is_empty() {
+        println!();
+    }
+    for link in &status.links {
+        println!("{}: {}", link.name, link.url);
+    }
+
+    if let Some(warning) = stale_files_warning(&status) {
+        println!("\n{warning}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+pub mod agents;
+pub mod conditions;
+pub mod constructions;
+pub mod galaxy;
+pub mod ledger;
+pub mod markets;
+pub mod scans;
+pub mod shipyards;
+pub mod surveys;
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The files and directories caching the universe, which is regenerated on every server reset
+pub const UNIVERSE_FILES: [&str; 8] = [
+    galaxy::SYSTEMS_FILE,
+    galaxy::WAYPOINTS_FILE,
+    galaxy::CRAWLED_SYSTEMS_FILE,
+    markets::MARKETS_DIRECTORY,
+    shipyards::SHIPYARDS_FILE,
+    surveys::SURVEYS_FILE,
+    scans::SCANS_FILE,
+    constructions::CONSTRUCTIONS_FILE,
+];
+
+/// The reset date of the server the cached universe files were built for
+pub const RESET_DATE_FILE: &str = "./reset_date.json";
+
+/// Load a JSON file, or the default value when it doesn't exist yet.
+/// A file that can't be read or parsed is an error, so it's not saved over.
+pub fn load<T: DeserializeOwned + Default>(path: &str) -> Result<T, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(T::default()),
+        Err(err) => return Err(format!("Failed to read {path}: {err}")),
+    };
+
+    serde_json::from_str(&content).map_err(|err| format!("Failed to parse {path}: {err}"))
+}
+
+pub fn save<T: Serialize>(path: &str, value: &T) -> Result<(), String> {
+    let content = serde_json::to_string(value)
+        .map_err(|err| format!("Failed to serialize {path}: {err}"))?;
+
+    std::fs::write(path, content)
+        .map_err(|err| format!("Failed to write {path}: {err}"))
+}
+
+/// When a file was last written, `None` when it doesn't exist
+pub fn modified_at(path: &str) -> Option<DateTime<Utc>> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(DateTime::from(modified))
+}
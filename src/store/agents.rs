@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use space_trader_api::models;
+
+use crate::symbols::system_symbol_of;
+
+const AGENTS_FILE: &str = "./agents.json";
+/// The number of samples kept per agent
+const HISTORY_LENGTH: usize = 500;
+
+/// Prevents the trackers from overwriting each other's samples
+static AGENTS_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// The credits and fleet of an agent at a given time
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AgentSample {
+    pub timestamp: DateTime<Utc>,
+    pub credits: i64,
+    pub ship_count: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentHistory {
+    pub symbol: String,
+    pub headquarters: String,
+    pub starting_faction: String,
+    /// Oldest first
+    pub samples: Vec<AgentSample>,
+}
+
+impl AgentHistory {
+    pub fn latest(&self) -> Option<&AgentSample> {
+        self.samples.last()
+    }
+
+    /// The credits earned over a period, from the last sample taken before it started
+    pub fn credits_growth(&self, period: TimeDelta) -> Option<i64> {
+        let latest = self.latest()?;
+        let start = self.samples
+            .iter()
+            .rev()
+            .find(|sample| sample.timestamp <= latest.timestamp - period)
+            .or(self.samples.first())?;
+
+        Some(latest.credits - start.credits)
+    }
+}
+
+/// The agents seen by the tracker, by agent symbol
+#[derive(Default, Serialize, Deserialize)]
+pub struct AgentStore {
+    agents: HashMap<String, AgentHistory>,
+}
+
+impl AgentStore {
+    pub fn load() -> Result<AgentStore, String> {
+        super::load(AGENTS_FILE)
+    }
+
+    pub fn get(&self, agent_symbol: &str) -> Option<&AgentHistory> {
+        self.agents.get(agent_symbol)
+    }
+
+    /// The agents sorted by a value of their latest sample, highest first
+    pub fn ranking(&self, key: impl Fn(&AgentHistory) -> i64) -> Vec<&AgentHistory> {
+        let mut ranking: Vec<&AgentHistory> = self.agents.values().filter(|agent| agent.latest().is_some()).collect();
+        ranking.sort_by_key(|agent| std::cmp::Reverse(key(agent)));
+        ranking
+    }
+
+    /// The number of agents headquartered in each system
+    pub fn headquarters(&self) -> HashMap<String, usize> {
+        let mut headquarters = HashMap::new();
+        for agent in self.agents.values() {
+            *headquarters.entry(system_symbol_of(&agent.headquarters).to_string()).or_default() += 1;
+        }
+
+        headquarters
+    }
+}
+
+/// Add a sample for each agent
+pub fn record(agents: Vec<models::Agent>) -> Result<(), String> {
+    let _lock = AGENTS_FILE_LOCK.lock().unwrap();
+
+    let mut store = AgentStore::load()?;
+    let timestamp = Utc::now();
+    for agent in agents {
+        let history = store.agents.entry(agent.symbol.clone()).or_insert_with(|| AgentHistory {
+            symbol: agent.symbol.clone(),
+            headquarters: agent.headquarters.clone(),
+            starting_faction: agent.starting_faction.clone(),
+            samples: Vec::new(),
+        });
+
+        history.samples.push(AgentSample { timestamp, credits: agent.credits, ship_count: agent.ship_count });
+        if history.samples.len() > HISTORY_LENGTH {
+            history.samples.remove(0);
+        }
+    }
+
+    super::save(AGENTS_FILE, &store)
+}
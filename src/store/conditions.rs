@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use space_trader_api::models;
+
+const CONDITIONS_FILE: &str = "./conditions.json";
+/// The number of samples kept per ship
+const HISTORY_LENGTH: usize = 200;
+
+/// Prevents the ships from overwriting each other's samples
+static CONDITIONS_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Component {
+    Frame,
+    Reactor,
+    Engine,
+}
+
+impl Component {
+    pub const ALL: [Component; 3] = [Component::Frame, Component::Reactor, Component::Engine];
+}
+
+impl std::fmt::Display for Component {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// The condition and integrity of a component, from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Wear {
+    pub condition: f64,
+    pub integrity: f64,
+}
+
+/// The wear of a ship's components at a given time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConditionSample {
+    pub timestamp: DateTime<Utc>,
+    pub frame: Wear,
+    pub reactor: Wear,
+    pub engine: Wear,
+}
+
+impl ConditionSample {
+    pub fn of(ship: &models::Ship) -> ConditionSample {
+        ConditionSample {
+            timestamp: Utc::now(),
+            frame: Wear { condition: ship.frame.condition, integrity: ship.frame.integrity },
+            reactor: Wear { condition: ship.reactor.condition, integrity: ship.reactor.integrity },
+            engine: Wear { condition: ship.engine.condition, integrity: ship.engine.integrity },
+        }
+    }
+
+    pub fn wear(&self, component: Component) -> Wear {
+        match component {
+            Component::Frame => self.frame,
+            Component::Reactor => self.reactor,
+            Component::Engine => self.engine,
+        }
+    }
+}
+
+/// The wear of the ships' components over time, by ship symbol
+#[derive(Default, Serialize, Deserialize)]
+pub struct ConditionStore {
+    ships: HashMap<String, Vec<ConditionSample>>,
+}
+
+impl ConditionStore {
+    pub fn load() -> Result<ConditionStore, String> {
+        super::load(CONDITIONS_FILE)
+    }
+
+    pub fn history(&self, ship_symbol: &str) -> &[ConditionSample] {
+        self.ships.get(ship_symbol).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// Record the wear of a ship's components when it changed since the last sample
+pub fn record(ship: &models::Ship) -> Result<(), String> {
+    let _lock = CONDITIONS_FILE_LOCK.lock().unwrap();
+
+    let mut store = ConditionStore::load()?;
+    let sample = ConditionSample::of(ship);
+    let history = store.ships.entry(ship.symbol.clone()).or_default();
+
+    let is_unchanged = history.last().is_some_and(|last| {
+        Component::ALL.iter().all(|component| last.wear(*component) == sample.wear(*component))
+    });
+    if is_unchanged {
+        return Ok(())
+    }
+
+    history.push(sample);
+    if history.len() > HISTORY_LENGTH {
+        history.remove(0);
+    }
+
+    super::save(CONDITIONS_FILE, &store)
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use space_trader_api::models;
+
+pub const CONSTRUCTIONS_FILE: &str = "./constructions.json";
+
+/// Prevents the ships from overwriting each other's construction sites
+static CONSTRUCTIONS_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// A construction site as it was seen at a given time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstructionSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub construction: models::Construction,
+}
+
+/// The construction sites we've seen, by waypoint symbol
+#[derive(Default, Serialize, Deserialize)]
+pub struct ConstructionStore {
+    sites: HashMap<String, ConstructionSnapshot>,
+}
+
+impl ConstructionStore {
+    pub fn load() -> Result<ConstructionStore, String> {
+        super::load(CONSTRUCTIONS_FILE)
+    }
+
+    pub fn get(&self, waypoint_symbol: &str) -> Option<&ConstructionSnapshot> {
+        self.sites.get(waypoint_symbol)
+    }
+
+    /// The construction sites, sorted by waypoint symbol
+    pub fn sites(&self) -> Vec<&ConstructionSnapshot> {
+        let mut sites: Vec<&ConstructionSnapshot> = self.sites.values().collect();
+        sites.sort_by(|a, b| a.construction.symbol.cmp(&b.construction.symbol));
+        sites
+    }
+}
+
+/// The units of a material still to supply
+pub fn missing_units(material: &models::ConstructionMaterial) -> i32 {
+    (material.required - material.fulfilled).max(0)
+}
+
+/// Add or replace a construction site in the store
+pub fn record(construction: models::Construction) -> Result<(), String> {
+    let _lock = CONSTRUCTIONS_FILE_LOCK.lock().unwrap();
+
+    let mut store = ConstructionStore::load()?;
+    store.sites.insert(construction.symbol.clone(), ConstructionSnapshot {
+        timestamp: Utc::now(),
+        construction,
+    });
+
+    super::save(CONSTRUCTIONS_FILE, &store)
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use space_trader_api::models::{self, WaypointTraitSymbol};
+
+/// Downloaded by the `--download-systems` flag
+pub const SYSTEMS_FILE: &str = "./systems.json";
+pub const WAYPOINTS_FILE: &str = "./waypoints.json";
+/// When the waypoints of every system were crawled, by system symbol
+pub const CRAWLED_SYSTEMS_FILE: &str = "./crawled_systems.json";
+
+/// Prevents the ships from overwriting each other's waypoints
+static WAYPOINTS_FILE_LOCK: Mutex<()> = Mutex::new(());
+/// Prevents the crawlers from overwriting each other's crawled systems
+static CRAWLED_SYSTEMS_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// The waypoints we've fetched with their traits, by waypoint symbol
+#[derive(Default)]
+pub struct GalaxyStore {
+    waypoints: HashMap<String, models::Waypoint>,
+    /// The symbols of the waypoints having each trait
+    traits: HashMap<WaypointTraitSymbol, Vec<String>>,
+}
+
+impl GalaxyStore {
+    pub fn load() -> Result<GalaxyStore, String> {
+        let waypoints: HashMap<String, models::Waypoint> = super::load(WAYPOINTS_FILE)?;
+
+        let mut traits: HashMap<WaypointTraitSymbol, Vec<String>> = HashMap::new();
+        for waypoint in waypoints.values() {
+            for waypoint_trait in &waypoint.traits {
+                traits.entry(waypoint_trait.symbol).or_default().push(waypoint.symbol.clone());
+            }
+        }
+
+        Ok(GalaxyStore { waypoints, traits })
+    }
+
+    pub fn waypoint(&self, waypoint_symbol: &str) -> Option<&models::Waypoint> {
+        self.waypoints.get(waypoint_symbol)
+    }
+
+    pub fn waypoints(&self) -> impl Iterator<Item = &models::Waypoint> {
+        self.waypoints.values()
+    }
+
+    /// The fetched waypoints of a system
+    pub fn waypoints_of<'a>(&'a self, system_symbol: &'a str) -> impl Iterator<Item = &'a models::Waypoint> {
+        self.waypoints.values().filter(move |waypoint| waypoint.system_symbol == system_symbol)
+    }
+
+    /// The fetched waypoints having a trait
+    pub fn with_trait(&self, symbol: WaypointTraitSymbol) -> impl Iterator<Item = &models::Waypoint> {
+        self.traits
+            .get(&symbol)
+            .into_iter()
+            .flatten()
+            .filter_map(|waypoint_symbol| self.waypoints.get(waypoint_symbol))
+    }
+
+    /// The fetched waypoints that were under construction when last seen
+    pub fn under_construction(&self) -> impl Iterator<Item = &models::Waypoint> {
+        self.waypoints.values().filter(|waypoint| waypoint.is_under_construction)
+    }
+}
+
+pub fn has_trait(waypoint: &models::Waypoint, symbol: WaypointTraitSymbol) -> bool {
+    waypoint.traits.iter().any(|waypoint_trait| waypoint_trait.symbol == symbol)
+}
+
+/// Add or replace waypoints in the store
+pub fn record(waypoints: Vec<models::Waypoint>) -> Result<(), String> {
+    let _lock = WAYPOINTS_FILE_LOCK.lock().unwrap();
+
+    let mut stored: HashMap<String, models::Waypoint> = super::load(WAYPOINTS_FILE)?;
+    for waypoint in waypoints {
+        stored.insert(waypoint.symbol.clone(), waypoint);
+    }
+
+    super::save(WAYPOINTS_FILE, &stored)
+}
+
+/// When the waypoints of the systems were crawled, by system symbol
+pub fn crawled_systems() -> Result<HashMap<String, DateTime<Utc>>, String> {
+    super::load(CRAWLED_SYSTEMS_FILE)
+}
+
+/// Remember that the waypoints of systems were crawled
+pub fn mark_crawled(system_symbols: &[String]) -> Result<(), String> {
+    let _lock = CRAWLED_SYSTEMS_FILE_LOCK.lock().unwrap();
+
+    let mut crawled = crawled_systems()?;
+    let timestamp = Utc::now();
+    for system_symbol in system_symbols {
+        crawled.insert(system_symbol.clone(), timestamp);
+    }
+
+    super::save(CRAWLED_SYSTEMS_FILE, &crawled)
+}
@@ -0,0 +1,417 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models::{self, market_transaction::Type as MarketTransactionType};
+
+use crate::automation::events::{emit, Event};
+use crate::automation::retry;
+use crate::cli::{flag_value, get_token};
+use crate::time;
+
+/// One entry per line, so entries are only ever appended
+const LEDGER_FILE: &str = "./ledger.jsonl";
+
+/// The strategy each ship is running, to attribute its transactions
+static STRATEGIES: LazyLock<Mutex<HashMap<String, String>>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EntryKind {
+    MarketPurchase,
+    MarketSale,
+    ShipPurchase,
+    ShipScrap,
+    ShipModification,
+    Repair,
+    ContractAccepted,
+    ContractFulfilled,
+}
+
+impl std::fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let kind = serde_json::to_value(self).ok();
+        write!(f, "{}", kind.as_ref().and_then(|kind| kind.as_str()).unwrap_or_default())
+    }
+}
+
+/// A credit movement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: EntryKind,
+    pub ship_symbol: Option<String>,
+    pub waypoint_symbol: Option<String>,
+    /// The traded good, the ship type or the mount, depending on the kind
+    pub good: Option<String>,
+    /// The contract paid, for the contract entries
+    #[serde(default)]
+    pub contract_id: Option<String>,
+    pub units: i32,
+    pub price_per_unit: i32,
+    /// The credits earned, negative when credits are spent
+    pub amount: i64,
+    /// The credits of the agent after the transaction, when known
+    pub credits_after: Option<i64>,
+    /// The strategy the ship was running, like mining or contracts
+    #[serde(default)]
+    pub strategy: Option<String>,
+}
+
+fn parse_timestamp(timestamp: &str) -> DateTime<Utc> {
+    time::parse(timestamp).unwrap_or_else(Utc::now)
+}
+
+impl LedgerEntry {
+    pub fn from_market(transaction: &models::MarketTransaction, agent: Option<&models::Agent>) -> LedgerEntry {
+        let (kind, amount) = match transaction.r#type {
+            MarketTransactionType::Purchase => (EntryKind::MarketPurchase, -(transaction.total_price as i64)),
+            MarketTransactionType::Sell => (EntryKind::MarketSale, transaction.total_price as i64),
+        };
+
+        LedgerEntry {
+            timestamp: parse_timestamp(&transaction.timestamp),
+            kind,
+            ship_symbol: Some(transaction.ship_symbol.clone()),
+            waypoint_symbol: Some(transaction.waypoint_symbol.clone()),
+            good: Some(transaction.trade_symbol.clone()),
+            contract_id: None,
+            units: transaction.units,
+            price_per_unit: transaction.price_per_unit,
+            amount,
+            credits_after: agent.map(|agent| agent.credits),
+            strategy: None,
+        }
+    }
+
+    pub fn from_shipyard(transaction: &models::ShipyardTransaction, agent: Option<&models::Agent>) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: parse_timestamp(&transaction.timestamp),
+            kind: EntryKind::ShipPurchase,
+            ship_symbol: Some(transaction.ship_symbol.clone()),
+            waypoint_symbol: Some(transaction.waypoint_symbol.clone()),
+            good: Some(transaction.ship_type.clone()),
+            contract_id: None,
+            units: 1,
+            price_per_unit: transaction.price,
+            amount: -(transaction.price as i64),
+            credits_after: agent.map(|agent| agent.credits),
+            strategy: None,
+        }
+    }
+
+    pub fn from_scrap(transaction: &models::ScrapTransaction, agent: Option<&models::Agent>) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: parse_timestamp(&transaction.timestamp),
+            kind: EntryKind::ShipScrap,
+            ship_symbol: Some(transaction.ship_symbol.clone()),
+            waypoint_symbol: Some(transaction.waypoint_symbol.clone()),
+            good: None,
+            contract_id: None,
+            units: 1,
+            price_per_unit: transaction.total_price,
+            amount: transaction.total_price as i64,
+            credits_after: agent.map(|agent| agent.credits),
+            strategy: None,
+        }
+    }
+
+    pub fn from_modification(transaction: &models::ShipModificationTransaction, agent: Option<&models::Agent>) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: parse_timestamp(&transaction.timestamp),
+            kind: EntryKind::ShipModification,
+            ship_symbol: Some(transaction.ship_symbol.clone()),
+            waypoint_symbol: Some(transaction.waypoint_symbol.clone()),
+            good: Some(transaction.trade_symbol.clone()),
+            contract_id: None,
+            units: 1,
+            price_per_unit: transaction.total_price,
+            amount: -(transaction.total_price as i64),
+            credits_after: agent.map(|agent| agent.credits),
+            strategy: None,
+        }
+    }
+
+    pub fn from_repair(transaction: &models::RepairTransaction, agent: Option<&models::Agent>) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: parse_timestamp(&transaction.timestamp),
+            kind: EntryKind::Repair,
+            ship_symbol: Some(transaction.ship_symbol.clone()),
+            waypoint_symbol: Some(transaction.waypoint_symbol.clone()),
+            good: None,
+            contract_id: None,
+            units: 1,
+            price_per_unit: transaction.total_price,
+            amount: -(transaction.total_price as i64),
+            credits_after: agent.map(|agent| agent.credits),
+            strategy: None,
+        }
+    }
+
+    /// The payment of a contract once accepted or fulfilled.
+    /// The contract responses don't date the payment, it's dated when recorded, right after the response.
+    pub fn from_contract(contract: &models::Contract, ship_symbol: Option<&str>, agent: &models::Agent) -> LedgerEntry {
+        let (kind, amount) = match contract.fulfilled {
+            false => (EntryKind::ContractAccepted, contract.terms.payment.on_accepted),
+            true => (EntryKind::ContractFulfilled, contract.terms.payment.on_fulfilled),
+        };
+
+        LedgerEntry {
+            timestamp: Utc::now(),
+            kind,
+            ship_symbol: ship_symbol.map(str::to_string),
+            waypoint_symbol: None,
+            good: None,
+            contract_id: Some(contract.id.clone()),
+            units: 1,
+            price_per_unit: amount,
+            amount: amount as i64,
+            credits_after: Some(agent.credits),
+            strategy: Some(String::from("contract")),
+        }
+    }
+}
+
+/// Attribute the next transactions of the ship to a strategy
+pub fn set_strategy(ship_symbol: &str, strategy: impl Into<String>) {
+    STRATEGIES.lock().unwrap().insert(ship_symbol.to_string(), strategy.into());
+}
+
+/// Stop attributing the transactions of the ship to its strategy, once its task is over
+pub fn clear_strategy(ship_symbol: &str) {
+    STRATEGIES.lock().unwrap().remove(ship_symbol);
+}
+
+/// Append an entry to the ledger and publish it to the event stream.
+/// Failing to write it doesn't stop the ships, it's only reported.
+pub fn record(mut entry: LedgerEntry) {
+    if entry.strategy.is_none() {
+        entry.strategy = entry.ship_symbol
+            .as_ref()
+            .and_then(|ship_symbol| STRATEGIES.lock().unwrap().get(ship_symbol).cloned());
+    }
+
+    let written = serde_json::to_string(&entry)
+        .map_err(|err| err.to_string())
+        .and_then(|line| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(LEDGER_FILE)
+                .and_then(|mut file| writeln!(file, "{line}"))
+                .map_err(|err| err.to_string())
+        });
+
+    if let Err(err) = written {
+        eprintln!("Failed to record a transaction in {LEDGER_FILE}: {err}");
+    }
+    emit(Event::Transaction { entry });
+}
+
+/// The income and expenses of a group of entries
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Totals {
+    pub income: i64,
+    pub expenses: i64,
+}
+
+impl Totals {
+    pub fn net(&self) -> i64 {
+        self.income - self.expenses
+    }
+
+    pub fn add(&mut self, amount: i64) {
+        match amount >= 0 {
+            true => self.income += amount,
+            false => self.expenses -= amount,
+        }
+    }
+}
+
+pub struct Ledger {
+    /// Sorted by timestamp
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl Ledger {
+    pub fn load() -> Ledger {
+        let mut entries: Vec<LedgerEntry> = std::fs::read_to_string(LEDGER_FILE)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        entries.sort_by_key(|entry| entry.timestamp);
+
+        Ledger { entries }
+    }
+
+    /// Group the entries by a key, like the ship or the day, and total them
+    pub fn totals_by<K: Ord>(&self, key: impl Fn(&LedgerEntry) -> K) -> BTreeMap<K, Totals> {
+        self.entries.iter().fold(BTreeMap::new(), |mut totals, entry| {
+            totals.entry(key(entry)).or_default().add(entry.amount);
+            totals
+        })
+    }
+
+    /// The credits the agent should have according to the ledger,
+    /// from the last entry with known credits
+    pub fn expected_credits(&self) -> Option<i64> {
+        let (index, anchor) = self.entries
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, entry)| entry.credits_after.is_some())?;
+
+        let after_anchor: i64 = self.entries[index + 1..].iter().map(|entry| entry.amount).sum();
+        Some(anchor.credits_after? + after_anchor)
+    }
+}
+
+/// `space-trader ledger [--by=ship|good|contract|day|kind] [--token=<token>]`
+///
+/// Print the totals of the ledger, and reconcile it against the agent's credits when a token is given
+pub async fn run_command(args: &[String]) -> Result<(), String> {
+    let ledger = Ledger::load();
+
+    let totals = match flag_value(args, "--by=").unwrap_or("day") {
+        "ship" => ledger.totals_by(|entry| entry.ship_symbol.clone().unwrap_or_default()),
+        "good" => ledger.totals_by(|entry| entry.good.clone().unwrap_or_default()),
+        "contract" => ledger.totals_by(|entry| entry.contract_id.clone().unwrap_or_default()),
+        "day" => ledger.totals_by(|entry| entry.timestamp.date_naive().to_string()),
+        "kind" => ledger.totals_by(|entry| entry.kind.to_string()),
+        by => return Err(format!("Unknown grouping {by}, expected ship, good, contract, day or kind")),
+    };
+
+    println!("{:<24} {:>12} {:>12} {:>12}", "", "income", "expenses", "net");
+    for (key, totals) in &totals {
+        println!("{key:<24} {:>12} {:>12} {:>12}", totals.income, totals.expenses, totals.net());
+    }
+
+    if let Some(token) = get_token(args) {
+        let config = Configuration::from_bearer_access_token(token);
+        let agent = retry(|| apis::agents_api::get_my_agent(&config))
+            .await
+            .map_err(|err| format!("Failed to get agent: {err}"))?
+            .data;
+
+        match ledger.expected_credits() {
+            Some(expected) if expected == agent.credits => println!("Reconciled: {} credits", agent.credits),
+            Some(expected) => println!(
+                "Mismatch: the ledger expects {expected} credits, the agent has {} ({} unrecorded)",
+                agent.credits, agent.credits - expected
+            ),
+            None => println!("Nothing to reconcile, the agent has {} credits", agent.credits),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMESTAMP: &str = "2024-03-01T12:00:00Z";
+
+    fn agent(credits: i64) -> models::Agent {
+        models::Agent::new("AGENT".into(), "X1-A1-A1".into(), credits, "COSMIC".into(), 1)
+    }
+
+    fn market_transaction(r#type: MarketTransactionType, total_price: i32) -> models::MarketTransaction {
+        models::MarketTransaction::new("X1-A1-B2".into(), "SHIP-1".into(), "IRON_ORE".into(), r#type, 10, total_price / 10, total_price, TIMESTAMP.into())
+    }
+
+    fn entry(amount: i64, credits_after: Option<i64>) -> LedgerEntry {
+        LedgerEntry { credits_after, ..LedgerEntry::from_market(&market_transaction(MarketTransactionType::Sell, amount as i32), None) }
+    }
+
+    #[test]
+    fn market_sale_earns_and_purchase_spends() {
+        let sale = LedgerEntry::from_market(&market_transaction(MarketTransactionType::Sell, 500), Some(&agent(1500)));
+        let purchase = LedgerEntry::from_market(&market_transaction(MarketTransactionType::Purchase, 300), None);
+
+        assert_eq!(sale.kind, EntryKind::MarketSale);
+        assert_eq!(sale.amount, 500);
+        assert_eq!(sale.price_per_unit, 50);
+        assert_eq!(sale.credits_after, Some(1500));
+        assert_eq!(sale.good.as_deref(), Some("IRON_ORE"));
+        assert_eq!(sale.timestamp, parse_timestamp(TIMESTAMP));
+        assert_eq!(purchase.kind, EntryKind::MarketPurchase);
+        assert_eq!(purchase.amount, -300);
+        assert_eq!(purchase.credits_after, None);
+    }
+
+    #[test]
+    fn ship_expenses_are_negative() {
+        let ship = LedgerEntry::from_shipyard(
+            &models::ShipyardTransaction::new("X1-A1-C3".into(), "SHIP-2".into(), "SHIP_PROBE".into(), 20000, "AGENT".into(), TIMESTAMP.into()),
+            None,
+        );
+        let mount = LedgerEntry::from_modification(
+            &models::ShipModificationTransaction::new("X1-A1-C3".into(), "SHIP-1".into(), "MOUNT_SURVEYOR_I".into(), 3000, TIMESTAMP.into()),
+            None,
+        );
+        let repair = LedgerEntry::from_repair(&models::RepairTransaction::new("X1-A1-C3".into(), "SHIP-1".into(), 800, TIMESTAMP.into()), None);
+        let scrap = LedgerEntry::from_scrap(&models::ScrapTransaction::new("X1-A1-C3".into(), "SHIP-3".into(), 5000, TIMESTAMP.into()), Some(&agent(7000)));
+
+        assert_eq!((ship.kind, ship.amount, ship.good.as_deref()), (EntryKind::ShipPurchase, -20000, Some("SHIP_PROBE")));
+        assert_eq!((mount.kind, mount.amount, mount.good.as_deref()), (EntryKind::ShipModification, -3000, Some("MOUNT_SURVEYOR_I")));
+        assert_eq!((repair.kind, repair.amount, repair.good), (EntryKind::Repair, -800, None));
+        assert_eq!((scrap.kind, scrap.amount, scrap.credits_after), (EntryKind::ShipScrap, 5000, Some(7000)));
+    }
+
+    #[test]
+    fn contract_payment_depends_on_fulfillment() {
+        let terms = models::ContractTerms::new(TIMESTAMP.into(), models::ContractPayment::new(1000, 9000));
+        let mut contract = models::Contract::new("CONTRACT-1".into(), "COSMIC".into(), models::contract::Type::Procurement, terms, true, false, TIMESTAMP.into());
+
+        let accepted = LedgerEntry::from_contract(&contract, Some("SHIP-1"), &agent(2000));
+        contract.fulfilled = true;
+        let fulfilled = LedgerEntry::from_contract(&contract, None, &agent(11000));
+
+        assert_eq!((accepted.kind, accepted.amount), (EntryKind::ContractAccepted, 1000));
+        assert_eq!((fulfilled.kind, fulfilled.amount), (EntryKind::ContractFulfilled, 9000));
+        assert_eq!(accepted.contract_id.as_deref(), Some("CONTRACT-1"));
+        assert_eq!(accepted.good, None);
+        assert_eq!(accepted.ship_symbol.as_deref(), Some("SHIP-1"));
+        assert_eq!(fulfilled.credits_after, Some(11000));
+        assert_eq!(fulfilled.strategy.as_deref(), Some("contract"));
+    }
+
+    #[test]
+    fn totals_split_income_and_expenses() {
+        let mut totals = Totals::default();
+        for amount in [500, -200, 300, -50] {
+            totals.add(amount);
+        }
+
+        assert_eq!((totals.income, totals.expenses, totals.net()), (800, 250, 550));
+    }
+
+    #[test]
+    fn totals_are_grouped_by_key() {
+        let ledger = Ledger { entries: vec![entry(100, None), entry(-40, None), entry(25, None)] };
+        let totals = ledger.totals_by(|entry| entry.amount > 0);
+
+        assert_eq!(totals[&true].income, 125);
+        assert_eq!(totals[&false].expenses, 40);
+    }
+
+    #[test]
+    fn expected_credits_from_the_last_known_credits() {
+        let ledger = Ledger { entries: vec![entry(100, Some(1000)), entry(-50, Some(950)), entry(30, None), entry(-10, None)] };
+
+        assert_eq!(ledger.expected_credits(), Some(970));
+    }
+
+    #[test]
+    fn nothing_to_reconcile_without_known_credits() {
+        let ledger = Ledger { entries: vec![entry(100, None)] };
+
+        assert_eq!(ledger.expected_credits(), None);
+        assert_eq!(Ledger { entries: Vec::new() }.expected_credits(), None);
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use space_trader_api::models;
+
+use crate::symbols::system_symbol_of;
+
+/// One file per market, so recording a market only rewrites its own history
+pub const MARKETS_DIRECTORY: &str = "./markets";
+/// The number of snapshots kept per market
+const HISTORY_LENGTH: usize = 50;
+
+/// Prevents the ships from overwriting each other's markets
+static MARKETS_DIRECTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// A market as it was seen at a given time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub market: models::Market,
+}
+
+/// The markets we've seen, by waypoint symbol
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MarketStore {
+    markets: HashMap<String, Vec<MarketSnapshot>>,
+}
+
+impl MarketStore {
+    pub fn load() -> Result<MarketStore, String> {
+        let entries = match std::fs::read_dir(MARKETS_DIRECTORY) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(MarketStore::default()),
+            Err(err) => return Err(format!("Failed to read {MARKETS_DIRECTORY}: {err}")),
+        };
+
+        let mut markets = HashMap::new();
+        for entry in entries {
+            let path = entry.map_err(|err| format!("Failed to read {MARKETS_DIRECTORY}: {err}"))?.path();
+            let Some(waypoint_symbol) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue
+            };
+            let history = super::load(&path.to_string_lossy())?;
+            markets.insert(waypoint_symbol.to_string(), history);
+        }
+
+        Ok(MarketStore { markets })
+    }
+
+    /// Record a market in its file and in the store
+    pub fn record(&mut self, market: models::Market) -> Result<(), String> {
+        let waypoint_symbol = market.symbol.clone();
+        let history = record(market)?;
+        self.markets.insert(waypoint_symbol, history);
+        Ok(())
+    }
+
+    pub fn latest(&self, waypoint_symbol: &str) -> Option<&MarketSnapshot> {
+        self.markets.get(waypoint_symbol)?.last()
+    }
+
+    /// The latest snapshot of every market of a system
+    pub fn latest_in<'a>(&'a self, system_symbol: &'a str) -> impl Iterator<Item = &'a MarketSnapshot> {
+        self.markets
+            .iter()
+            .filter(move |(waypoint_symbol, _)| system_symbol_of(waypoint_symbol) == system_symbol)
+            .filter_map(|(_, history)| history.last())
+    }
+
+    /// When the prices of every market were last seen, by waypoint symbol
+    pub fn priced_at(&self) -> HashMap<String, DateTime<Utc>> {
+        self.markets
+            .iter()
+            .filter_map(|(waypoint_symbol, history)| {
+                let snapshot = history.iter().rev().find(|snapshot| snapshot.market.trade_goods.is_some())?;
+                Some((waypoint_symbol.clone(), snapshot.timestamp))
+            })
+            .collect()
+    }
+
+    /// The latest known prices of a good at a market.
+    /// Prices are only known when one of our ships was at the market.
+    pub fn latest_trade_good(&self, waypoint_symbol: &str, good: models::TradeSymbol) -> Option<&models::MarketTradeGood> {
+        self.markets
+            .get(waypoint_symbol)?
+            .iter()
+            .rev()
+            .find_map(|snapshot| snapshot.market.trade_goods.as_ref())?
+            .iter()
+            .find(|trade_good| trade_good.symbol == good)
+    }
+
+    /// The latest known prices of a good at every market trading it, with when they were seen
+    pub fn trade_goods(&self, good: models::TradeSymbol) -> impl Iterator<Item = (&str, DateTime<Utc>, &models::MarketTradeGood)> {
+        self.markets.iter().filter_map(move |(waypoint_symbol, history)| {
+            let snapshot = history.iter().rev().find(|snapshot| snapshot.market.trade_goods.is_some())?;
+            let trade_good = snapshot.market.trade_goods.iter().flatten().find(|trade_good| trade_good.symbol == good)?;
+            Some((waypoint_symbol.as_str(), snapshot.timestamp, trade_good))
+        })
+    }
+
+    /// The goods whose prices were seen at least once
+    pub fn priced_goods(&self) -> BTreeSet<models::TradeSymbol> {
+        self.markets
+            .values()
+            .flatten()
+            .flat_map(|snapshot| snapshot.market.trade_goods.iter().flatten())
+            .map(|trade_good| trade_good.symbol)
+            .collect()
+    }
+
+    /// The best known price a good can be sold at in a system
+    pub fn best_sell_price(&self, system_symbol: &str, good: models::TradeSymbol) -> Option<i32> {
+        self.markets
+            .keys()
+            .filter(|waypoint_symbol| system_symbol_of(waypoint_symbol) == system_symbol)
+            .filter_map(|waypoint_symbol| self.latest_trade_good(waypoint_symbol, good))
+            .map(|trade_good| trade_good.sell_price)
+            .max()
+    }
+}
+
+/// Add a snapshot of a market to its history file, returning the history
+pub fn record(market: models::Market) -> Result<Vec<MarketSnapshot>, String> {
+    let _lock = MARKETS_DIRECTORY_LOCK.lock().unwrap();
+
+    let path = format!("{MARKETS_DIRECTORY}/{}.json", market.symbol);
+    let mut history: Vec<MarketSnapshot> = super::load(&path)?;
+    history.push(MarketSnapshot {
+        timestamp: Utc::now(),
+        market,
+    });
+    if history.len() > HISTORY_LENGTH {
+        history.remove(0);
+    }
+
+    std::fs::create_dir_all(MARKETS_DIRECTORY)
+        .map_err(|err| format!("Failed to create {MARKETS_DIRECTORY}: {err}"))?;
+    super::save(&path, &history)?;
+    Ok(history)
+}
+
+/// Whether the good can be bought at the market
+pub fn sells(market: &models::Market, good: models::TradeSymbol) -> bool {
+    market.exports.iter().chain(&market.exchange).any(|trade_good| trade_good.symbol == good)
+}
+
+/// Whether the good can be sold at the market
+pub fn buys(market: &models::Market, good: models::TradeSymbol) -> bool {
+    market.imports.iter().chain(&market.exchange).any(|trade_good| trade_good.symbol == good)
+}
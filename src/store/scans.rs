@@ -0,0 +1,162 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use space_trader_api::models::{self, ShipNavStatus};
+
+use crate::time;
+
+pub const SCANS_FILE: &str = "./scans.json";
+/// The number of scans kept
+const HISTORY_LENGTH: usize = 100;
+
+/// Prevents the ships from overwriting each other's scans
+static SCANS_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanKind {
+    Systems,
+    Waypoints,
+    Ships,
+}
+
+impl ScanKind {
+    pub const ALL: [ScanKind; 3] = [ScanKind::Systems, ScanKind::Waypoints, ScanKind::Ships];
+
+    /// Parse the `--kind` argument
+    pub fn parse(kind: &str) -> Option<ScanKind> {
+        ScanKind::ALL.into_iter().find(|scan_kind| scan_kind.to_string().eq_ignore_ascii_case(kind))
+    }
+}
+
+impl std::fmt::Display for ScanKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// What the sensors of a ship detected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "results", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ScanResults {
+    Systems(Vec<models::ScannedSystem>),
+    Waypoints(Vec<models::ScannedWaypoint>),
+    Ships(Vec<models::ScannedShip>),
+}
+
+impl ScanResults {
+    pub fn kind(&self) -> ScanKind {
+        match self {
+            ScanResults::Systems(_) => ScanKind::Systems,
+            ScanResults::Waypoints(_) => ScanKind::Waypoints,
+            ScanResults::Ships(_) => ScanKind::Ships,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        match self {
+            ScanResults::Systems(systems) => systems.len(),
+            ScanResults::Waypoints(waypoints) => waypoints.len(),
+            ScanResults::Ships(ships) => ships.len(),
+        }
+    }
+
+    /// One line per detected system, waypoint or ship
+    pub fn describe(&self) -> Vec<String> {
+        match self {
+            ScanResults::Systems(systems) => systems
+                .iter()
+                .map(|system| format!("{} ({}) at {}", system.symbol, system.r#type, system.distance))
+                .collect(),
+            ScanResults::Waypoints(waypoints) => waypoints
+                .iter()
+                .map(|waypoint| {
+                    let traits: Vec<&str> = waypoint.traits.iter().map(|waypoint_trait| waypoint_trait.name.as_str()).collect();
+                    format!("{} ({}): {}", waypoint.symbol, waypoint.r#type, traits.join(", "))
+                })
+                .collect(),
+            ScanResults::Ships(ships) => ships.iter().map(describe_ship).collect(),
+        }
+    }
+}
+
+fn describe_ship(ship: &models::ScannedShip) -> String {
+    let registration = format!("{} ({}, {})", ship.registration.name, ship.registration.role, ship.registration.faction_symbol);
+    let location = match ship.nav.status {
+        ShipNavStatus::InTransit => format!(
+            "{} to {}, arriving in {}",
+            ship.nav.route.origin.symbol, ship.nav.route.destination.symbol, time::format_countdown(&ship.nav.route.arrival)
+        ),
+        _ => ship.nav.waypoint_symbol.clone(),
+    };
+
+    format!("{registration} {} {location}", ship.nav.status)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scan {
+    pub timestamp: DateTime<Utc>,
+    pub ship_symbol: String,
+    /// Where the ship was when it scanned
+    pub waypoint_symbol: String,
+    #[serde(flatten)]
+    pub results: ScanResults,
+}
+
+/// The last scans of every ship, oldest first
+#[derive(Default, Serialize, Deserialize)]
+pub struct ScanStore {
+    scans: Vec<Scan>,
+}
+
+impl ScanStore {
+    pub fn load() -> Result<ScanStore, String> {
+        super::load(SCANS_FILE)
+    }
+
+    /// The scans, newest first
+    pub fn history(&self) -> impl Iterator<Item = &Scan> {
+        self.scans.iter().rev()
+    }
+
+    /// The last time each ship was seen by our sensors, by ship symbol
+    pub fn sightings(&self) -> HashMap<&str, (DateTime<Utc>, &models::ScannedShip)> {
+        let mut sightings = HashMap::new();
+        for scan in &self.scans {
+            if let ScanResults::Ships(ships) = &scan.results {
+                for ship in ships {
+                    sightings.insert(ship.symbol.as_str(), (scan.timestamp, ship));
+                }
+            }
+        }
+
+        sightings
+    }
+
+    /// The symbols of the waypoints revealed by the scans
+    pub fn scanned_waypoints(&self) -> HashSet<&str> {
+        self.scans
+            .iter()
+            .filter_map(|scan| match &scan.results {
+                ScanResults::Waypoints(waypoints) => Some(waypoints),
+                _ => None,
+            })
+            .flatten()
+            .map(|waypoint| waypoint.symbol.as_str())
+            .collect()
+    }
+}
+
+/// Add a scan to the history, dropping the oldest ones
+pub fn record(scan: Scan) -> Result<(), String> {
+    let _lock = SCANS_FILE_LOCK.lock().unwrap();
+
+    let mut store = ScanStore::load()?;
+    store.scans.push(scan);
+    if store.scans.len() > HISTORY_LENGTH {
+        store.scans.remove(0);
+    }
+
+    super::save(SCANS_FILE, &store)
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use space_trader_api::models::{self, ship_module::Symbol as ModuleSymbol};
+
+pub const SHIPYARDS_FILE: &str = "./shipyards.json";
+
+/// A shipyard as it was last seen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShipyardSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub shipyard: models::Shipyard,
+    /// When the ships and their prices were seen, they're kept from an earlier visit
+    #[serde(default)]
+    pub ships_seen_at: Option<DateTime<Utc>>,
+}
+
+impl ShipyardSnapshot {
+    /// When the ships were seen, the snapshots recorded before it was tracked falling back to their timestamp
+    pub fn ships_timestamp(&self) -> DateTime<Utc> {
+        self.ships_seen_at.unwrap_or(self.timestamp)
+    }
+}
+
+/// The shipyards we've seen, by waypoint symbol
+#[derive(Default, Serialize, Deserialize)]
+pub struct ShipyardStore {
+    shipyards: HashMap<String, ShipyardSnapshot>,
+}
+
+impl ShipyardStore {
+    pub fn load() -> Result<ShipyardStore, String> {
+        super::load(SHIPYARDS_FILE)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        super::save(SHIPYARDS_FILE, self)
+    }
+
+    /// Keep the ships of the previous visit when none of our ships is at the shipyard
+    pub fn record(&mut self, mut shipyard: models::Shipyard) {
+        let now = Utc::now();
+        let mut ships_seen_at = shipyard.ships.is_some().then_some(now);
+        if let Some(previous) = self.shipyards.get(&shipyard.symbol) {
+            if shipyard.ships.is_none() && previous.shipyard.ships.is_some() {
+                shipyard.ships = previous.shipyard.ships.clone();
+                ships_seen_at = Some(previous.ships_timestamp());
+            }
+        }
+
+        self.shipyards.insert(shipyard.symbol.clone(), ShipyardSnapshot {
+            timestamp: now,
+            shipyard,
+            ships_seen_at,
+        });
+    }
+
+    pub fn get(&self, waypoint_symbol: &str) -> Option<&ShipyardSnapshot> {
+        self.shipyards.get(waypoint_symbol)
+    }
+
+    /// The known prices of a ship type at every shipyard, the cheapest first.
+    /// Prices are only known when one of our ships was at the shipyard.
+    pub fn prices(&self, ship_type: models::ShipType) -> Vec<(&str, i32, DateTime<Utc>)> {
+        let mut prices: Vec<_> = self.shipyards
+            .iter()
+            .filter_map(|(waypoint_symbol, snapshot)| {
+                let ship = snapshot.shipyard.ships.iter().flatten().find(|ship| ship.r#type == ship_type)?;
+                Some((waypoint_symbol.as_str(), ship.purchase_price, snapshot.ships_timestamp()))
+            })
+            .collect();
+        prices.sort_by_key(|(_, price, _)| *price);
+
+        prices
+    }
+
+    /// The ships sold by the shipyards
+    pub fn ships(&self) -> impl Iterator<Item = &models::ShipyardShip> {
+        self.shipyards
+            .values()
+            .flat_map(|snapshot| snapshot.shipyard.ships.iter().flatten())
+    }
+
+    /// The mounts installed on the ships sold by the shipyards
+    pub fn mounts(&self) -> impl Iterator<Item = &models::ShipMount> {
+        self.ships().flat_map(|ship| &ship.mounts)
+    }
+}
+
+/// The cargo capacity given by the cargo hold modules of a ship
+pub fn cargo_capacity(ship: &models::ShipyardShip) -> i32 {
+    ship.modules
+        .iter()
+        .filter(|module| matches!(module.symbol, ModuleSymbol::CargoHoldI | ModuleSymbol::CargoHoldIi | ModuleSymbol::CargoHoldIii))
+        .filter_map(|module| module.capacity)
+        .sum()
+}
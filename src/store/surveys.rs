@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use space_trader_api::models;
+
+use crate::time;
+
+pub const SURVEYS_FILE: &str = "./surveys.json";
+
+/// Prevents the mining ships from overwriting each other's surveys
+static SURVEYS_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// The surveys shared by every mining ship, by waypoint symbol.
+/// Surveys are kept until they expire or get exhausted.
+#[derive(Default, Serialize, Deserialize)]
+pub struct SurveyStore {
+    surveys: HashMap<String, Vec<models::Survey>>,
+}
+
+impl SurveyStore {
+    pub fn load() -> Result<SurveyStore, String> {
+        let mut store: SurveyStore = super::load(SURVEYS_FILE)?;
+        store.prune();
+        Ok(store)
+    }
+
+    /// The unexpired surveys of a waypoint
+    pub fn at(&self, waypoint_symbol: &str) -> impl Iterator<Item = &models::Survey> {
+        self.surveys
+            .get(waypoint_symbol)
+            .into_iter()
+            .flatten()
+            .filter(|survey| !is_expired(survey))
+    }
+
+    /// Remove the expired surveys
+    fn prune(&mut self) {
+        for surveys in self.surveys.values_mut() {
+            surveys.retain(|survey| !is_expired(survey));
+        }
+        self.surveys.retain(|_, surveys| !surveys.is_empty());
+    }
+}
+
+/// Add surveys to the store
+pub fn add(surveys: Vec<models::Survey>) -> Result<(), String> {
+    let _lock = SURVEYS_FILE_LOCK.lock().unwrap();
+
+    let mut store = SurveyStore::load()?;
+    for survey in surveys {
+        store.surveys.entry(survey.symbol.clone()).or_default().push(survey);
+    }
+
+    super::save(SURVEYS_FILE, &store)
+}
+
+/// Remove a survey from the store, when it's exhausted for example
+pub fn remove(signature: &str) -> Result<(), String> {
+    let _lock = SURVEYS_FILE_LOCK.lock().unwrap();
+
+    let mut store = SurveyStore::load()?;
+    for surveys in store.surveys.values_mut() {
+        surveys.retain(|survey| survey.signature != signature);
+    }
+    store.prune();
+
+    super::save(SURVEYS_FILE, &store)
+}
+
+pub fn is_expired(survey: &models::Survey) -> bool {
+    time::parse(&survey.expiration).is_none_or(|expiration| expiration <= Utc::now())
+}
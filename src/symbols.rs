@@ -0,0 +1,32 @@
+use space_trader_api::models::{ship_mount::Symbol as MountSymbol, TradeSymbol, WaypointTraitSymbol};
+
+/// Get the symbol of the system containing a waypoint
+/// (`X1-DF55-20250Z` is in the system `X1-DF55`)
+pub fn system_symbol_of(waypoint_symbol: &str) -> &str {
+    waypoint_symbol
+        .rsplit_once('-')
+        .map_or(waypoint_symbol, |(system, _)| system)
+}
+
+/// Parse a trade symbol given as a string by the API, like `IRON_ORE`
+pub fn parse_trade_symbol(symbol: &str) -> Option<TradeSymbol> {
+    serde_json::from_value(serde_json::Value::String(symbol.to_string())).ok()
+}
+
+/// Parse a waypoint trait given as a string, like `SHIPYARD`
+pub fn parse_waypoint_trait_symbol(symbol: &str) -> Option<WaypointTraitSymbol> {
+    serde_json::from_value(serde_json::Value::String(symbol.to_string())).ok()
+}
+
+/// The mount installed from a good, like `MOUNT_SURVEYOR_I`
+pub fn parse_mount_symbol(good: TradeSymbol) -> Option<MountSymbol> {
+    serde_json::to_value(good).ok().and_then(|symbol| serde_json::from_value(symbol).ok())
+}
+
+/// The symbol of a mount as given to the API
+pub fn mount_symbol_name(symbol: MountSymbol) -> String {
+    serde_json::to_value(symbol)
+        .ok()
+        .and_then(|symbol| symbol.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+
+/// Parse a timestamp returned by the API (RFC 3339)
+pub fn parse(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Format the time left until `timestamp`, like `2d 4h 12m`
+pub fn format_countdown(timestamp: &str) -> String {
+    match parse(timestamp) {
+        Some(date) => format_time_left(date),
+        None => String::from("unknown"),
+    }
+}
+
+/// Format the time left until `date`, like `2d 4h 12m`
+pub fn format_time_left(date: DateTime<Utc>) -> String {
+    let seconds = (date - Utc::now()).num_seconds();
+    if seconds <= 0 {
+        return String::from("expired")
+    }
+
+    format_duration(seconds)
+}
+
+/// Format the time elapsed since `date`, like `4h 12m ago`
+pub fn format_age(date: DateTime<Utc>) -> String {
+    format!("{} ago", format_duration((Utc::now() - date).num_seconds().max(0)))
+}
+
+fn format_duration(seconds: i64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+    match (days, hours) {
+        (0, 0) => format!("{minutes}m {}s", seconds % 60),
+        (0, _) => format!("{hours}h {minutes}m"),
+        _ => format!("{days}d {hours}h {minutes}m"),
+    }
+}
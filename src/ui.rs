@@ -1,60 +1,95 @@
 mod types;
 mod view;
 mod galaxy;
+mod contracts;
+mod report;
+mod loadout;
+mod shipyard;
+mod scans;
+mod construction;
+mod status;
+mod competitors;
+mod filter;
+mod waypoint;
+mod market;
+mod heatmap;
+mod quadtree;
+mod camera;
+mod clip;
+mod coordinates;
 
 use types::*;
+use clip::Clip;
+use coordinates::GalaxyCoordinates;
+use quadtree::QuadTree;
 
+use space_trader_api::apis::configuration::Configuration;
 use space_trader_api::models;
-use iced::{event, executor, mouse, widget, window, Application, Command, Element, Event, Length, Result, Settings, Size, Theme, Vector};
+use iced::widget::{button, column, container, pick_list, row, text};
+use iced::{executor, widget, window, Application, Command, Element, Length, Result, Settings, Size, Theme};
+
+use crate::automation::get_my_ships;
+use crate::store::galaxy::SYSTEMS_FILE;
+use crate::store::scans::ScanStore;
+use crate::store::shipyards::ShipyardStore;
 
 
 const WINDOW_SIZE: Size = Size::new(1024.0, 768.0);
 
-pub fn run() -> Result {
+const PANEL_WIDTH: f32 = 380.0;
+
+/// How far the mouse can move between a press and a release for a click
+const CLICK_TOLERANCE: f32 = 3.;
+
+const MARKETS_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How often the map is redrawn to age the markets and move the scanned ships along their route
+const MAP_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+pub fn run(token: Option<String>) -> Result {
     App::run(Settings {
         antialiasing: true,
         window: window::Settings {
             size: WINDOW_SIZE,
             ..window::Settings::default()
         },
+        flags: Flags { token },
         ..Settings::default()
     })
 }
 
+fn load_ships(config: Configuration) -> Command<Message> {
+    Command::perform(
+        async move {
+            get_my_ships(&config)
+                .await
+                .map_err(|err| format!("Failed to get ships: {err}"))
+        },
+        Message::ShipsLoaded
+    )
+}
+
 impl Application for App {
     type Message = Message;
-    type Flags = ();
+    type Flags = Flags;
     type Executor = executor::Default;
     type Theme = Theme;
 
-    fn new(_flags: Self::Flags) -> (Self, Command<Message>) {
-        let systems_file = std::fs::read_to_string("./systems.json").unwrap();
+    fn new(flags: Self::Flags) -> (Self, Command<Message>) {
+        let systems_file = std::fs::read_to_string(SYSTEMS_FILE).unwrap();
         let systems: Vec<models::System> = serde_json::from_str(&systems_file).unwrap();
-        let galaxy = {
-            let galaxy_radius = galaxy::get_galaxy_radius(&systems);
-
-            // Calculate the base scale for the systems to fit the screen
-            let base_scale = {
-                let window_size = WINDOW_SIZE.height.min(WINDOW_SIZE.width);
-                window_size / (galaxy_radius * 2.)
-            };
-
+        let positions: Vec<iced::Point> = systems
+            .iter()
+            .map(|system| iced::Point::new(system.x as f32, system.y as f32))
+            .collect();
+        let coordinates = GalaxyCoordinates::new(&positions);
+        let galaxy: Vec<SizedSystem> = {
             let galaxy_color_gradient = galaxy::color::get_gradient();
 
             systems
                 .iter()
                 .map(|system| {
-                    let mut new_system =
-                        SizedSystem::from_system(
-                            system,
-                            iced::Point::new(WINDOW_SIZE.width/2., WINDOW_SIZE.height/2.),
-                            galaxy_radius
-                        )
-                        .apply_scale(base_scale)      // Scale the system to fit the screen
-                        .offset_position(Vector::new( // Center the galaxy
-                            WINDOW_SIZE.width/2.,
-                            WINDOW_SIZE.height/2.
-                        ));
+                    let mut new_system = SizedSystem::from_system(system, &coordinates);
 
                     // Set the color of the system
                     new_system.color = galaxy::color::get_system_color(
@@ -66,14 +101,38 @@ impl Application for App {
                 })
                 .collect()
         };
+        let system_index = QuadTree::new(
+            galaxy.iter().map(|system| (iced::Point::new(system.x, system.y), system.radius())).collect()
+        );
+        let system_indices = galaxy.iter().enumerate().map(|(index, system)| (system.symbol.clone(), index)).collect();
 
-        (
-            App {
-                galaxy,
-                ..App::default()
-            },
-            Command::none(),
-        )
+        let config = flags.token.map(Configuration::from_bearer_access_token);
+        // The server status tells whether the cached data predates the last reset
+        let command = match &config {
+            Some(config) => Command::batch([
+                status::load(),
+                market::load(),
+                load_ships(config.clone()),
+                contracts::load(config.clone()),
+            ]),
+            None => Command::batch([status::load(), market::load()]),
+        };
+
+        let mut app = App {
+            galaxy,
+            coordinates,
+            system_index,
+            system_indices,
+            config,
+            ..App::default()
+        };
+        match ScanStore::load() {
+            Ok(scans) => app.scans = scans,
+            Err(err) => app.status = Some(err),
+        }
+        competitors::load_agents(&mut app);
+
+        (app, command)
     }
 
     fn title(&self) -> String {
@@ -84,62 +143,155 @@ impl Application for App {
         match message {
             Message::MouseWheelScrolled(scroll_delta) => {
                 let zoom_sensibility = 0.1;
-                let scale = (scroll_delta * zoom_sensibility).exp().max(0.01);
-
-                let mouse = self.nav.mouse_current_position - self.nav.offset;
-
-                self.zoom = Zoom {
-                    scale: self.zoom.scale * scale,
-                    offset: Vector::new(
-                        self.zoom.offset.x * scale + (mouse.x - mouse.x * scale),
-                        self.zoom.offset.y * scale + (mouse.y - mouse.y * scale),
-                    ),
-                };
-                self.cache.clear();
+                let factor = (scroll_delta * zoom_sensibility).exp();
+                self.camera.zoom_at(factor, self.nav.mouse_current_position, self.coordinates.bounds);
             }
             Message::MouseDown => {
-                self.nav.offset_start_position = Some(self.nav.mouse_current_position - self.nav.offset);
+                self.nav.drag_start = Some((self.nav.mouse_current_position, self.camera.center));
+                self.nav.pressed_at = Some(self.nav.mouse_current_position);
             }
             Message::MouseUp => {
-                self.nav.offset_start_position = None;
+                self.nav.drag_start = None;
+
+                let position = self.nav.mouse_current_position;
+                let is_click = self.nav.pressed_at
+                    .take()
+                    .is_some_and(|pressed_at| pressed_at.distance(position) <= CLICK_TOLERANCE);
+                if let Some(waypoint_symbol) = is_click.then(|| view::waypoint_at(self, position)).flatten() {
+                    return waypoint::select(self, waypoint_symbol)
+                }
             }
             Message::MouseMoved(position) => {
                 self.nav.mouse_current_position = position;
 
-                if let Some(mouse_down_position) = self.nav.offset_start_position {
-                    self.nav.offset = position - mouse_down_position;
-                    self.cache.clear();
+                if let Some((mouse_start, camera_start)) = self.nav.drag_start {
+                    self.camera.pan(camera_start, position - mouse_start);
                 }
             }
+            Message::ViewportResized(size) => self.camera.resize(size, self.coordinates.bounds),
+            Message::FitGalaxy => self.camera.fit(self.coordinates.bounds),
+            Message::FitSelection => match camera::selection(self) {
+                Some(area) => self.camera.fit(area),
+                None => self.status = Some(String::from("Nothing to fit, filter the systems or select a waypoint or a ship")),
+            },
+            Message::Tick => {}
+            Message::RefreshMarkets => return market::load(),
+            Message::RefreshMap => self.cache.clear(),
+            Message::TogglePanel(panel) => {
+                self.panel = match self.panel {
+                    Some(opened) if opened == panel => None,
+                    _ => Some(panel),
+                };
+                match self.panel {
+                    Some(Panel::Report) => report::update(self, report::Message::Refresh),
+                    Some(Panel::Shipyard | Panel::Loadout) => match ShipyardStore::load() {
+                        Ok(shipyards) => self.shipyards = shipyards,
+                        Err(err) => self.status = Some(err),
+                    },
+                    Some(Panel::Scans) => match ScanStore::load() {
+                        Ok(scans) => self.scans = scans,
+                        Err(err) => self.status = Some(err),
+                    },
+                    Some(Panel::Competitors) => competitors::load_agents(self),
+                    Some(Panel::Filter) => filter::load(self),
+                    Some(Panel::Heatmap) => return market::load(),
+                    Some(Panel::Construction) => return construction::update(self, construction::Message::Refresh),
+                    _ => {}
+                }
+            }
+            Message::ShipSelected(ship_symbol) => self.selected_ship = Some(ship_symbol),
+            Message::ShipsLoaded(Ok(ships)) => self.ships = ships,
+            Message::ShipsLoaded(Err(err)) => self.status = Some(err),
+            Message::Contracts(message) => return contracts::update(self, message),
+            Message::Report(message) => report::update(self, message),
+            Message::Shipyard(message) => return shipyard::update(self, message),
+            Message::Loadout(message) => return loadout::update(self, message),
+            Message::Scans(message) => return scans::update(self, message),
+            Message::Construction(message) => return construction::update(self, message),
+            Message::Status(message) => return status::update(self, message),
+            Message::Competitors(message) => return competitors::update(self, message),
+            Message::Filter(message) => filter::update(self, message),
+            Message::Waypoint(message) => return waypoint::update(self, message),
+            Message::Market(message) => return market::update(self, message),
+            Message::Heatmap(message) => heatmap::update(self, message),
         }
 
         Command::none()
     }
 
-    fn view(&self) -> Element<Message> {
-        widget::Canvas::new(self)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
+    fn view(&self) -> Element<'_, Message> {
+        let ship_symbols: Vec<String> = self.ships.iter().map(|ship| ship.symbol.clone()).collect();
+        let toolbar = row![
+            button("Fit galaxy").on_press(Message::FitGalaxy),
+            button("Fit selection").on_press(Message::FitSelection),
+            button("Contracts").on_press(Message::TogglePanel(Panel::Contracts)),
+            button("Report").on_press(Message::TogglePanel(Panel::Report)),
+            button("Shipyards").on_press(Message::TogglePanel(Panel::Shipyard)),
+            button("Loadout").on_press(Message::TogglePanel(Panel::Loadout)),
+            button("Scans").on_press(Message::TogglePanel(Panel::Scans)),
+            button("Construction").on_press(Message::TogglePanel(Panel::Construction)),
+            button("Server").on_press(Message::TogglePanel(Panel::Status)),
+            button("Competitors").on_press(Message::TogglePanel(Panel::Competitors)),
+            button("Filter").on_press(Message::TogglePanel(Panel::Filter)),
+            button("Heatmap").on_press(Message::TogglePanel(Panel::Heatmap)),
+            pick_list(ship_symbols, self.selected_ship.clone(), Message::ShipSelected)
+                .placeholder("Select a ship"),
+            text(self.status.as_deref().unwrap_or_default()).size(14),
+        ]
+        .spacing(10)
+        .padding(5);
+
+        let map = Clip {
+            content: widget::Canvas::new(self)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into(),
+            viewport: self.camera.viewport,
+        };
+
+        let content: Element<Message> = match self.panel {
+            None => map.into(),
+            Some(panel) => {
+                let panel_content = match panel {
+                    Panel::Contracts => contracts::view(self),
+                    Panel::Report => report::view(self),
+                    Panel::Shipyard => shipyard::view(self),
+                    Panel::Loadout => loadout::view(self),
+                    Panel::Scans => scans::view(self),
+                    Panel::Construction => construction::view(self),
+                    Panel::Status => status::view(self),
+                    Panel::Competitors => competitors::view(self),
+                    Panel::Filter => filter::view(self),
+                    Panel::Waypoint => waypoint::view(self),
+                    Panel::Market => market::view(self),
+                    Panel::Heatmap => heatmap::view(self),
+                };
+
+                row![
+                    map,
+                    container(panel_content)
+                        .width(PANEL_WIDTH)
+                        .height(Length::Fill)
+                        .padding(10),
+                ].into()
+            }
+        };
+
+        column![toolbar, content].into()
     }
 
     fn subscription(&self) -> iced::Subscription<Message> {
-        event::listen_with(|event, _| match event {
-            Event::Mouse(mouse_event) => match mouse_event {
-                mouse::Event::WheelScrolled {
-                    delta: mouse::ScrollDelta::Pixels { x: _, y },
-                }
-                | mouse::Event::WheelScrolled {
-                    delta: mouse::ScrollDelta::Lines { x: _, y },
-                } => Some(Message::MouseWheelScrolled(y)),
+        // Refresh the countdowns
+        let countdowns = match self.panel {
+            Some(Panel::Contracts | Panel::Scans | Panel::Status) => iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::Tick),
+            Some(Panel::Report | Panel::Shipyard | Panel::Loadout | Panel::Construction | Panel::Competitors | Panel::Filter | Panel::Waypoint | Panel::Market | Panel::Heatmap) | None => iced::Subscription::none(),
+        };
+        // The probes keep updating the markets
+        let markets = iced::time::every(MARKETS_REFRESH_INTERVAL).map(|_| Message::RefreshMarkets);
 
-                mouse::Event::ButtonPressed(mouse::Button::Left) => Some(Message::MouseDown),
-                mouse::Event::ButtonReleased(mouse::Button::Left) => Some(Message::MouseUp),
+        // The cached map shows the age of what was seen
+        let map = iced::time::every(MAP_REFRESH_INTERVAL).map(|_| Message::RefreshMap);
 
-                mouse::Event::CursorMoved { position } => Some(Message::MouseMoved(position)),
-                _ => None,
-            },
-            _ => None
-        })
+        iced::Subscription::batch([countdowns, markets, map])
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,255 @@
+use iced::{Point, Rectangle, Size, Vector};
+
+use super::quadtree::bounding_box;
+use super::types::App;
+use crate::symbols::system_symbol_of;
+
+/// The zoom tiers per doubling of the scale, the map being redrawn when changing tier
+const TIERS_PER_DOUBLING: f32 = 2.;
+/// How much smaller than the map the galaxy can be zoomed out to
+const MAX_ZOOM_OUT: f32 = 0.5;
+/// The largest number of pixels per unit of the galaxy
+const MAX_SCALE: f32 = 10.;
+/// The part of the map filled by a fitted area
+const FIT_MARGIN: f32 = 0.9;
+
+/// The part of the galaxy shown on the map
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    /// The position of the galaxy at the center of the map
+    pub center: Point,
+    /// The pixels per unit of the galaxy
+    pub scale: f32,
+    /// The size of the map
+    pub viewport: Size,
+    /// Whether the size of the map is known, it's only known once laid out
+    pub is_sized: bool,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Camera {
+            center: Point::ORIGIN,
+            scale: 1.,
+            viewport: Size::new(1., 1.),
+            is_sized: false,
+        }
+    }
+}
+
+impl Camera {
+    /// Show a whole area on the map
+    pub fn fit(&mut self, area: Rectangle) {
+        let scale = (self.viewport.width / area.width.max(1.)).min(self.viewport.height / area.height.max(1.)) * FIT_MARGIN;
+        self.center = area.center();
+        self.scale = scale.min(MAX_SCALE);
+    }
+
+    /// Follow the size of the map, showing the whole galaxy once its first size is known
+    pub fn resize(&mut self, viewport: Size, galaxy: Rectangle) {
+        self.viewport = viewport;
+        if !self.is_sized {
+            self.is_sized = true;
+            self.fit(galaxy);
+        }
+    }
+
+    /// Where a position of the galaxy is on the map
+    pub fn screen_position(&self, position: Point) -> Point {
+        Point::new(
+            (position.x - self.center.x) * self.scale + self.viewport.width / 2.,
+            (position.y - self.center.y) * self.scale + self.viewport.height / 2.,
+        )
+    }
+
+    /// Where a position of the map is in the galaxy
+    pub fn world_position(&self, position: Point) -> Point {
+        Point::new(
+            (position.x - self.viewport.width / 2.) / self.scale + self.center.x,
+            (position.y - self.viewport.height / 2.) / self.scale + self.center.y,
+        )
+    }
+
+    /// The part of the galaxy on the map
+    pub fn visible_area(&self) -> Rectangle {
+        Rectangle::new(
+            self.world_position(Point::ORIGIN),
+            Size::new(self.viewport.width / self.scale, self.viewport.height / self.scale),
+        )
+    }
+
+    /// Zoom keeping the position under the cursor in place, without zooming out further than the galaxy
+    pub fn zoom_at(&mut self, factor: f32, cursor: Point, galaxy: Rectangle) {
+        let anchor = self.world_position(cursor);
+        let min_scale = {
+            let mut fitted = *self;
+            fitted.fit(galaxy);
+            fitted.scale * MAX_ZOOM_OUT
+        };
+        self.scale = (self.scale * factor).clamp(min_scale, MAX_SCALE);
+        self.center = Point::new(
+            anchor.x - (cursor.x - self.viewport.width / 2.) / self.scale,
+            anchor.y - (cursor.y - self.viewport.height / 2.) / self.scale,
+        );
+    }
+
+    /// Move the galaxy by a distance on the screen
+    pub fn pan(&mut self, from: Point, delta: Vector) {
+        self.center = from - delta * (1. / self.scale);
+    }
+
+    /// The zoom tier, the geometry drawn at the scale of a tier is reused within it
+    pub fn tier(&self) -> i32 {
+        (self.scale.log2() * TIERS_PER_DOUBLING).floor() as i32
+    }
+
+    /// The scale the geometry of the tier is drawn at
+    pub fn tier_scale(&self) -> f32 {
+        (self.tier() as f32 / TIERS_PER_DOUBLING).exp2()
+    }
+}
+
+/// The area covered by the selection: the systems matching the filter, highlighted by the contracts,
+/// of the selected waypoint and of the selected ship
+pub fn selection(app: &App) -> Option<Rectangle> {
+    let waypoint_system = app.selected_waypoint.as_deref().map(system_symbol_of);
+    let ship_system = app.selected_ship
+        .as_ref()
+        .and_then(|ship_symbol| app.ships.iter().find(|ship| ship.symbol == *ship_symbol))
+        .map(|ship| ship.nav.system_symbol.as_str());
+
+    let mut systems = app.galaxy
+        .iter()
+        .filter(|system| {
+            app.filtered_systems.contains_key(&system.symbol)
+                || app.highlighted_systems.contains(&system.symbol)
+                || waypoint_system == Some(system.symbol.as_str())
+                || ship_system == Some(system.symbol.as_str())
+        })
+        .peekable();
+    systems.peek()?;
+
+    Some(bounding_box(systems.map(|system| (Point::new(system.x, system.y), system.radius()))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn assert_near(actual: Point, expected: Point) {
+        assert!(actual.distance(expected) < EPSILON, "{actual:?} isn't {expected:?}");
+    }
+
+    fn camera() -> Camera {
+        Camera {
+            center: Point::new(100., -50.),
+            scale: 2.,
+            viewport: Size::new(800., 600.),
+            is_sized: true,
+        }
+    }
+
+    #[test]
+    fn center_is_at_the_middle_of_the_map() {
+        let camera = camera();
+
+        assert_near(camera.screen_position(camera.center), Point::new(400., 300.));
+        assert_near(camera.screen_position(Point::new(110., -50.)), Point::new(420., 300.));
+    }
+
+    #[test]
+    fn screen_and_world_positions_are_inverse() {
+        let camera = camera();
+
+        for position in [Point::ORIGIN, Point::new(123., -456.), Point::new(-1e4, 3e4)] {
+            assert_near(camera.world_position(camera.screen_position(position)), position);
+        }
+    }
+
+    #[test]
+    fn visible_area_covers_the_map() {
+        let camera = camera();
+        let area = camera.visible_area();
+
+        assert_near(area.position(), Point::new(-100., -200.));
+        assert_eq!(area.size(), Size::new(400., 300.));
+        assert_near(camera.screen_position(Point::new(area.x + area.width, area.y + area.height)), Point::new(800., 600.));
+    }
+
+    #[test]
+    fn zoom_keeps_the_position_under_the_cursor() {
+        let mut camera = camera();
+        let galaxy = Rectangle::new(Point::new(-1000., -1000.), Size::new(2000., 2000.));
+        let cursor = Point::new(200., 450.);
+        let anchor = camera.world_position(cursor);
+
+        camera.zoom_at(1.5, cursor, galaxy);
+
+        assert_eq!(camera.scale, 3.);
+        assert_near(camera.world_position(cursor), anchor);
+    }
+
+    #[test]
+    fn zoom_is_clamped() {
+        let mut camera = camera();
+        let galaxy = Rectangle::new(Point::new(-1000., -1000.), Size::new(2000., 2000.));
+
+        camera.zoom_at(1e6, Point::ORIGIN, galaxy);
+        assert_eq!(camera.scale, MAX_SCALE);
+
+        // The galaxy fits in 600 * 0.9 pixels, and can be zoomed out to half of that
+        camera.zoom_at(1e-6, Point::ORIGIN, galaxy);
+        assert!((camera.scale - 0.27 * MAX_ZOOM_OUT).abs() < EPSILON);
+    }
+
+    #[test]
+    fn fit_shows_the_whole_area() {
+        let mut camera = camera();
+        let area = Rectangle::new(Point::new(500., 500.), Size::new(1000., 200.));
+
+        camera.fit(area);
+
+        assert_near(camera.center, Point::new(1000., 600.));
+        assert!(camera.visible_area().contains(Point::new(500., 500.)));
+        assert!(camera.visible_area().contains(Point::new(1500., 700.)));
+        assert!((camera.scale - 0.72).abs() < EPSILON);
+    }
+
+    #[test]
+    fn galaxy_is_fitted_to_the_first_size() {
+        let mut camera = Camera::default();
+        let galaxy = Rectangle::new(Point::new(-1000., -1000.), Size::new(2000., 2000.));
+
+        camera.resize(Size::new(800., 600.), galaxy);
+        assert_near(camera.center, Point::ORIGIN);
+        assert!((camera.scale - 0.27).abs() < EPSILON);
+
+        // Resizing the window afterwards keeps the zoom
+        camera.resize(Size::new(400., 300.), galaxy);
+        assert!((camera.scale - 0.27).abs() < EPSILON);
+        assert_eq!(camera.viewport, Size::new(400., 300.));
+    }
+
+    #[test]
+    fn pan_follows_the_mouse() {
+        let mut camera = camera();
+        let from = camera.center;
+        let grabbed = camera.world_position(Point::new(300., 300.));
+
+        camera.pan(from, Vector::new(50., -20.));
+
+        assert_near(camera.screen_position(grabbed), Point::new(350., 280.));
+    }
+
+    #[test]
+    fn tier_scale_is_close_to_the_scale() {
+        for scale in [0.01, 0.3, 1., 1.9, 2., 7.5] {
+            let camera = Camera { scale, ..camera() };
+            let tier_scale = camera.tier_scale();
+
+            assert!(tier_scale <= scale + EPSILON && scale < tier_scale * 2_f32.sqrt() + EPSILON, "{scale} in tier {tier_scale}");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use iced::advanced::widget::{tree, Tree};
+use iced::advanced::{layout, mouse, renderer, Clipboard, Layout, Renderer as _, Shell, Widget};
+use iced::{event, Element, Event, Length, Rectangle, Renderer, Size, Theme};
+
+use super::types::Message;
+
+/// Clip the map to its bounds, and report its size when it changes.
+/// The cached geometry of the map is moved by the camera and overflows the canvas.
+pub struct Clip<'a> {
+    pub content: Element<'a, Message>,
+    /// The size of the map known by the camera
+    pub viewport: Size,
+}
+
+impl Widget<Message, Theme, Renderer> for Clip<'_> {
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(&self, tree: &mut Tree, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(tree, renderer, limits)
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        renderer.with_layer(layout.bounds(), |renderer| {
+            self.content.as_widget().draw(tree, renderer, theme, style, layout, cursor, viewport);
+        });
+    }
+
+    fn tag(&self) -> tree::Tag {
+        self.content.as_widget().tag()
+    }
+
+    fn state(&self) -> tree::State {
+        self.content.as_widget().state()
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        self.content.as_widget().children()
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        self.content.as_widget().diff(tree);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        // Every event is still given to the map, which needs its size to handle it.
+        // The redraw requested before the first frame reports the size as soon as the map is laid out.
+        let size = layout.bounds().size();
+        if size != self.viewport {
+            shell.publish(Message::ViewportResized(size));
+        }
+
+        self.content.as_widget_mut().on_event(tree, event, layout, cursor, renderer, clipboard, shell, viewport)
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(tree, layout, cursor, viewport, renderer)
+    }
+}
+
+impl<'a> From<Clip<'a>> for Element<'a, Message> {
+    fn from(clip: Clip<'a>) -> Self {
+        Element::new(clip)
+    }
+}
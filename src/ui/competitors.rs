@@ -0,0 +1,129 @@
+use iced::widget::{button, canvas, column, pick_list, row, scrollable, text, Canvas, Column};
+use iced::{mouse, Color, Command, Element, Length, Point, Rectangle, Renderer, Theme};
+
+use super::types::App;
+use crate::competitors::{self, Ranking, GROWTH_PERIOD};
+use crate::store::agents::{AgentSample, AgentStore};
+
+/// The number of agents listed in the panel
+const RANKING_LENGTH: usize = 50;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Sample every agent
+    Refresh,
+    Tracked(Result<usize, String>),
+    RankBy(Ranking),
+    Selected(String),
+}
+
+/// Reload the agents and their headquarters
+pub fn load_agents(app: &mut App) {
+    match AgentStore::load() {
+        Ok(agents) => app.agents = agents,
+        Err(err) => app.status = Some(err),
+    }
+    app.headquarters = app.agents.headquarters();
+    app.cache.clear();
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    match message {
+        Message::Refresh => {
+            app.status = Some(String::from("Sampling the agents"));
+            return Command::perform(competitors::track(), |res| super::Message::Competitors(Message::Tracked(res)))
+        }
+        Message::Tracked(Ok(count)) => {
+            app.status = Some(format!("Sampled {count} agents"));
+            load_agents(app);
+        }
+        Message::Tracked(Err(err)) => app.status = Some(err),
+        Message::RankBy(ranking) => app.agent_ranking = ranking,
+        Message::Selected(agent_symbol) => app.selected_agent = Some(agent_symbol),
+    }
+
+    Command::none()
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let ranking = app.agents
+        .ranking(|agent| app.agent_ranking.value(agent))
+        .into_iter()
+        .take(RANKING_LENGTH)
+        .enumerate()
+        .fold(Column::new().spacing(2), |column, (rank, agent)| {
+            let value = app.agent_ranking.value(agent);
+            column.push(
+                button(row![
+                    text(format!("{}. {}", rank + 1, agent.symbol)).size(14).width(Length::Fill),
+                    text(value.to_string()).size(14),
+                ])
+                .width(Length::Fill)
+                .style(iced::theme::Button::Text)
+                .on_press(super::Message::Competitors(Message::Selected(agent.symbol.clone())))
+            )
+        });
+
+    let selected = app.selected_agent.as_deref().and_then(|agent_symbol| app.agents.get(agent_symbol));
+    let details: Element<_> = match selected.and_then(|agent| Some((agent, agent.latest()?))) {
+        Some((agent, sample)) => column![
+            text(format!("{} ({}), headquarters {}", agent.symbol, agent.starting_faction, agent.headquarters)).size(16),
+            text(format!(
+                "{} credits, {} ships, {} credits in {}h",
+                sample.credits, sample.ship_count, agent.credits_growth(GROWTH_PERIOD).unwrap_or_default(), GROWTH_PERIOD.num_hours()
+            )).size(14),
+            Canvas::new(GrowthChart(&agent.samples)).width(Length::Fill).height(100),
+        ].spacing(5).into(),
+        None => text("Select an agent to see its growth").size(14).into(),
+    };
+
+    column![
+        row![
+            button("Refresh").on_press(super::Message::Competitors(Message::Refresh)),
+            pick_list(&Ranking::ALL[..], Some(app.agent_ranking), |ranking| super::Message::Competitors(Message::RankBy(ranking))),
+        ].spacing(10),
+        details,
+        scrollable(ranking).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+/// The credits of an agent over time as a line
+struct GrowthChart<'a>(&'a [AgentSample]);
+
+impl canvas::Program<super::Message> for GrowthChart<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<<Renderer as canvas::Renderer>::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let (Some(first), Some(last)) = (self.0.first(), self.0.last()) else {
+            return vec![frame.into_geometry()]
+        };
+
+        let min = self.0.iter().map(|sample| sample.credits).min().unwrap_or_default();
+        let max = self.0.iter().map(|sample| sample.credits).max().unwrap_or_default();
+        let duration = (last.timestamp - first.timestamp).num_seconds().max(1) as f32;
+        let position = |sample: &AgentSample| Point::new(
+            (sample.timestamp - first.timestamp).num_seconds() as f32 / duration * bounds.width,
+            bounds.height - (sample.credits - min) as f32 / (max - min).max(1) as f32 * bounds.height,
+        );
+
+        let line = canvas::Path::new(|builder| {
+            builder.move_to(position(first));
+            for sample in &self.0[1..] {
+                builder.line_to(position(sample));
+            }
+        });
+        frame.stroke(&line, canvas::Stroke::default().with_color(Color::from_rgb(0.2, 0.8, 0.2)).with_width(2.));
+
+        vec![frame.into_geometry()]
+    }
+}
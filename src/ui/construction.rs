@@ -0,0 +1,136 @@
+use space_trader_api::models;
+use iced::widget::{button, column, progress_bar, row, scrollable, text, Column};
+use iced::{Command, Element, Length};
+
+use super::types::App;
+use crate::automation::construction::{describe_source, fetch_construction, find_source, known_sites};
+use crate::automation::tasks::{self, Task};
+use crate::store::constructions::{missing_units, ConstructionSnapshot, ConstructionStore};
+use crate::store::markets::MarketStore;
+use crate::symbols::system_symbol_of;
+use crate::time;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Refresh,
+    Loaded(Result<(), String>),
+    /// Queue a task supplying the construction site for the selected ship
+    Assign(String),
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    match message {
+        Message::Refresh => {
+            load_sources(app);
+            let Some(config) = app.config.clone() else {
+                return Command::none()
+            };
+
+            return Command::perform(
+                async move {
+                    for waypoint_symbol in known_sites()? {
+                        fetch_construction(&config, &waypoint_symbol).await?;
+                    }
+                    Ok(())
+                },
+                |res| super::Message::Construction(Message::Loaded(res))
+            )
+        }
+        Message::Loaded(Ok(())) => load_sources(app),
+        Message::Loaded(Err(err)) => app.status = Some(err),
+        Message::Assign(waypoint_symbol) => {
+            let Some(ship_symbol) = app.selected_ship.clone() else {
+                app.status = Some(String::from("Select a ship to supply the construction site"));
+                return Command::none()
+            };
+
+            let task = Task::Construction { waypoint_symbol };
+            app.status = Some(match tasks::push(&ship_symbol, task.clone()) {
+                Ok(()) => format!("Assigned {task} to {ship_symbol}, run by the daemon"),
+                Err(err) => err,
+            });
+        }
+    }
+
+    Command::none()
+}
+
+/// Reload the construction sites and where to buy their missing materials
+fn load_sources(app: &mut App) {
+    let (constructions, markets) = match ConstructionStore::load().and_then(|constructions| Ok((constructions, MarketStore::load()?))) {
+        Ok(stores) => stores,
+        Err(err) => {
+            app.status = Some(err);
+            return
+        }
+    };
+    app.constructions = constructions;
+    app.construction_sources = app.constructions
+        .sites()
+        .into_iter()
+        .flat_map(|snapshot| {
+            let system_symbol = system_symbol_of(&snapshot.construction.symbol).to_string();
+            snapshot.construction.materials.iter().map(move |material| (system_symbol.clone(), material.trade_symbol))
+        })
+        .filter_map(|(system_symbol, good)| {
+            let source = find_source(&markets, &system_symbol, good)?;
+            Some(((system_symbol, good), source))
+        })
+        .collect();
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let sites = app.constructions
+        .sites()
+        .into_iter()
+        .fold(Column::new().spacing(20), |column, snapshot| column.push(site_view(app, snapshot)));
+
+    column![
+        button("Refresh").on_press(super::Message::Construction(Message::Refresh)),
+        scrollable(sites).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn site_view<'a>(app: &'a App, snapshot: &'a ConstructionSnapshot) -> Element<'a, super::Message> {
+    let construction = &snapshot.construction;
+    let system_symbol = system_symbol_of(&construction.symbol);
+
+    let materials = construction.materials
+        .iter()
+        .fold(Column::new().spacing(5), |column, material| {
+            let source = match (missing_units(material), app.construction_sources.get(&(system_symbol.to_string(), material.trade_symbol))) {
+                (0, _) => String::from("supplied"),
+                (_, Some(source)) => describe_source(source),
+                (_, None) => String::from("no known market sells it"),
+            };
+            column.push(material_view(material, source))
+        });
+
+    let status = match construction.is_complete {
+        true => String::from("Complete"),
+        false => format!("Under construction, seen {}", time::format_age(snapshot.timestamp)),
+    };
+
+    column![
+        row![
+            text(&construction.symbol).size(18).width(Length::Fill),
+            button("Supply with ship").on_press_maybe(
+                (!construction.is_complete).then(|| super::Message::Construction(Message::Assign(construction.symbol.clone())))
+            ),
+        ].spacing(10),
+        text(status).size(14),
+        materials,
+    ]
+    .spacing(5)
+    .into()
+}
+
+fn material_view<'a>(material: &models::ConstructionMaterial, source: String) -> Element<'a, super::Message> {
+    column![
+        text(format!("{}: {}/{}, {source}", material.trade_symbol, material.fulfilled, material.required)).size(14),
+        progress_bar(0.0..=material.required.max(1) as f32, material.fulfilled as f32).height(8),
+    ]
+    .into()
+}
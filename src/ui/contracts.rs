@@ -0,0 +1,251 @@
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+use iced::widget::{button, column, progress_bar, row, scrollable, text, Column};
+use iced::{Command, Element, Length};
+
+use super::types::App;
+use crate::automation::{get_contracts, retry};
+use crate::store::ledger::{self, LedgerEntry};
+use crate::{symbols, time};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Refresh,
+    Loaded(Result<Vec<models::Contract>, String>),
+    Accept(String),
+    Negotiate,
+    Deliver(String),
+    Fulfill(String),
+    /// A contract was accepted, negotiated, delivered or fulfilled
+    Updated(Result<models::Contract, String>),
+    /// Goods were delivered by a ship, with the contract and the remaining cargo after its last successful delivery,
+    /// and the error that stopped the deliveries
+    Delivered(String, Option<(models::Contract, Box<models::ShipCargo>)>, Result<(), String>),
+}
+
+pub fn load(config: Configuration) -> Command<super::Message> {
+    Command::perform(
+        async move {
+            get_contracts(&config)
+                .await
+                .map_err(|err| format!("Failed to get contracts: {err}"))
+        },
+        |res| super::Message::Contracts(Message::Loaded(res))
+    )
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    let Some(config) = app.config.clone() else {
+        app.status = Some(String::from("A token is required to manage contracts"));
+        return Command::none()
+    };
+
+    match message {
+        Message::Refresh => return load(config),
+        Message::Loaded(Ok(contracts)) => {
+            app.contracts = contracts;
+            highlight_destinations(app);
+        }
+        Message::Updated(Ok(contract)) => replace_contract(app, contract),
+        Message::Delivered(ship_symbol, delivered, res) => {
+            if let Some((contract, cargo)) = delivered {
+                if let Some(ship) = app.ships.iter_mut().find(|s| s.symbol == ship_symbol) {
+                    ship.cargo = cargo;
+                }
+                replace_contract(app, contract);
+            }
+            if let Err(err) = res {
+                app.status = Some(err);
+            }
+        }
+        Message::Loaded(Err(err))
+        | Message::Updated(Err(err)) => app.status = Some(err),
+
+        Message::Accept(contract_id) => return Command::perform(
+            async move {
+                retry(|| apis::contracts_api::accept_contract(&config, &contract_id))
+                    .await
+                    .map(|res| {
+                        ledger::record(LedgerEntry::from_contract(&res.data.contract, None, &res.data.agent));
+                        *res.data.contract
+                    })
+                    .map_err(|err| format!("Failed to accept contract: {err}"))
+            },
+            |res| super::Message::Contracts(Message::Updated(res))
+        ),
+        Message::Fulfill(contract_id) => return Command::perform(
+            async move {
+                retry(|| apis::contracts_api::fulfill_contract(&config, &contract_id))
+                    .await
+                    .map(|res| {
+                        ledger::record(LedgerEntry::from_contract(&res.data.contract, None, &res.data.agent));
+                        *res.data.contract
+                    })
+                    .map_err(|err| format!("Failed to fulfill contract: {err}"))
+            },
+            |res| super::Message::Contracts(Message::Updated(res))
+        ),
+        Message::Negotiate => {
+            let Some(ship_symbol) = app.selected_ship.clone() else {
+                app.status = Some(String::from("Select a ship docked at a faction headquarters to negotiate a contract"));
+                return Command::none()
+            };
+
+            return Command::perform(
+                async move {
+                    retry(|| apis::fleet_api::negotiate_contract(&config, &ship_symbol))
+                        .await
+                        .map(|res| *res.data.contract)
+                        .map_err(|err| format!("Failed to negotiate contract: {err}"))
+                },
+                |res| super::Message::Contracts(Message::Updated(res))
+            )
+        }
+        Message::Deliver(contract_id) => {
+            let Some(ship) = app.selected_ship.as_ref().and_then(|symbol| app.ships.iter().find(|s| &s.symbol == symbol)) else {
+                app.status = Some(String::from("Select a ship to deliver from"));
+                return Command::none()
+            };
+            let Some(contract) = app.contracts.iter().find(|c| c.id == contract_id) else {
+                return Command::none()
+            };
+
+            let deliveries = deliverable_goods(contract, &ship.cargo);
+            if deliveries.is_empty() {
+                app.status = Some(format!("{} has nothing to deliver for this contract", ship.symbol));
+                return Command::none()
+            }
+
+            let ship_symbol = ship.symbol.clone();
+            return Command::perform(
+                deliver(config, contract_id, ship_symbol.clone(), deliveries),
+                move |(delivered, res)| super::Message::Contracts(Message::Delivered(ship_symbol, delivered, res))
+            )
+        }
+    }
+
+    Command::none()
+}
+
+/// The goods of the ship's cargo that can be delivered for a contract, with their units
+fn deliverable_goods(contract: &models::Contract, cargo: &models::ShipCargo) -> Vec<(String, i32)> {
+    contract.terms.deliver
+        .iter()
+        .flatten()
+        .filter_map(|good| {
+            let in_cargo = cargo.inventory
+                .iter()
+                .find(|item| item.symbol.to_string() == good.trade_symbol)?
+                .units;
+            let units = in_cargo.min(good.units_required - good.units_fulfilled);
+
+            (units > 0).then(|| (good.trade_symbol.clone(), units))
+        })
+        .collect()
+}
+
+/// Deliver the goods one by one, stopping at the first failure.
+/// The contract and cargo of the last successful delivery are kept so a failure doesn't hide the previous ones.
+async fn deliver(
+    config: Configuration,
+    contract_id: String,
+    ship_symbol: String,
+    deliveries: Vec<(String, i32)>,
+) -> (Option<(models::Contract, Box<models::ShipCargo>)>, Result<(), String>) {
+    let mut delivered = None;
+
+    for (trade_symbol, units) in deliveries {
+        let request = models::DeliverContractRequest::new(ship_symbol.clone(), trade_symbol.clone(), units);
+        match retry(|| apis::contracts_api::deliver_contract(&config, &contract_id, Some(request.clone()))).await {
+            Ok(res) => delivered = Some((*res.data.contract, res.data.cargo)),
+            Err(err) => return (delivered, Err(format!("Failed to deliver {trade_symbol}: {err}"))),
+        }
+    }
+
+    (delivered, Ok(()))
+}
+
+/// Replace the contract with its latest version, adding it when it's new
+fn replace_contract(app: &mut App, contract: models::Contract) {
+    match app.contracts.iter_mut().find(|c| c.id == contract.id) {
+        Some(existing) => *existing = contract,
+        None => app.contracts.push(contract),
+    }
+    highlight_destinations(app);
+}
+
+/// Highlight the delivery destinations of the active contracts
+fn highlight_destinations(app: &mut App) {
+    app.highlighted_systems = app.contracts
+        .iter()
+        .filter(|contract| !contract.fulfilled)
+        .flat_map(|contract| contract.terms.deliver.iter().flatten())
+        .map(|good| symbols::system_symbol_of(&good.destination_symbol).to_string())
+        .collect();
+
+    app.cache.clear();
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let contracts = app.contracts
+        .iter()
+        .fold(Column::new().spacing(20), |column, contract| column.push(contract_view(contract)));
+
+    column![
+        row![
+            button("Refresh").on_press(super::Message::Contracts(Message::Refresh)),
+            button("Negotiate new").on_press(super::Message::Contracts(Message::Negotiate)),
+        ].spacing(10),
+        scrollable(contracts).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn contract_view(contract: &models::Contract) -> Element<'_, super::Message> {
+    let deadline = match (contract.accepted, &contract.deadline_to_accept) {
+        (false, Some(deadline_to_accept)) => format!("Accept within {}", time::format_countdown(deadline_to_accept)),
+        _ => format!("Deadline in {}", time::format_countdown(&contract.terms.deadline)),
+    };
+
+    let deliveries = contract.terms.deliver
+        .iter()
+        .flatten()
+        .fold(Column::new().spacing(5), |column, good| column.push(column![
+            text(format!(
+                "{} to {}: {}/{}",
+                good.trade_symbol, good.destination_symbol, good.units_fulfilled, good.units_required
+            )).size(14),
+            progress_bar(0.0..=good.units_required as f32, good.units_fulfilled as f32).height(8),
+        ]));
+
+    let is_delivered = contract.terms.deliver
+        .iter()
+        .flatten()
+        .all(|good| good.units_fulfilled >= good.units_required);
+
+    let id = contract.id.clone();
+    let actions = if contract.fulfilled {
+        row![text("Fulfilled")]
+    } else if !contract.accepted {
+        row![button("Accept").on_press(super::Message::Contracts(Message::Accept(id)))]
+    } else {
+        row![
+            button("Deliver from ship").on_press(super::Message::Contracts(Message::Deliver(id.clone()))),
+            button("Fulfill").on_press_maybe(is_delivered.then_some(super::Message::Contracts(Message::Fulfill(id)))),
+        ].spacing(10)
+    };
+
+    column![
+        text(format!("{} - {:?}", contract.faction_symbol, contract.r#type)).size(18),
+        text(deadline).size(14),
+        text(format!(
+            "Payment: {} on accepted, {} on fulfilled",
+            contract.terms.payment.on_accepted, contract.terms.payment.on_fulfilled
+        )).size(14),
+        deliveries,
+        actions,
+    ]
+    .spacing(5)
+    .into()
+}
@@ -0,0 +1,117 @@
+use iced::{Point, Rectangle, Size};
+
+/// Where the systems are in the galaxy, independently of how the map shows them
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GalaxyCoordinates {
+    /// The bounding box of the systems
+    pub bounds: Rectangle,
+    /// The mean position of the systems
+    pub centroid: Point,
+    /// The distance from the centroid to the farthest system
+    pub radius: f32,
+}
+
+impl GalaxyCoordinates {
+    pub fn new(positions: &[Point]) -> GalaxyCoordinates {
+        if positions.is_empty() {
+            return GalaxyCoordinates {
+                bounds: Rectangle::new(Point::ORIGIN, Size::ZERO),
+                centroid: Point::ORIGIN,
+                radius: 0.,
+            }
+        }
+
+        let (min, max, sum) = positions.iter().fold(
+            (Point::new(f32::MAX, f32::MAX), Point::new(f32::MIN, f32::MIN), (0_f64, 0_f64)),
+            |(min, max, sum), position| (
+                Point::new(min.x.min(position.x), min.y.min(position.y)),
+                Point::new(max.x.max(position.x), max.y.max(position.y)),
+                (sum.0 + position.x as f64, sum.1 + position.y as f64),
+            ),
+        );
+        // Summed in f64 so the thousands of systems don't lose precision
+        let count = positions.len() as f64;
+        let centroid = Point::new((sum.0 / count) as f32, (sum.1 / count) as f32);
+        let radius = positions.iter().fold(0_f32, |radius, position| radius.max(position.distance(centroid)));
+
+        GalaxyCoordinates {
+            bounds: Rectangle::new(min, Size::new(max.x - min.x, max.y - min.y)),
+            centroid,
+            radius,
+        }
+    }
+
+    /// The distance from the centroid, from 0 at the centroid to 1 for the farthest system
+    pub fn radial_distance(&self, position: Point) -> f32 {
+        match self.radius > 0. {
+            true => position.distance(self.centroid) / self.radius,
+            false => 0.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centroid_is_the_mean_position() {
+        let coordinates = GalaxyCoordinates::new(&[
+            Point::new(0., 0.),
+            Point::new(10., 0.),
+            Point::new(10., 10.),
+            Point::new(0., 10.),
+            Point::new(5., 5.),
+        ]);
+
+        assert_eq!(coordinates.centroid, Point::new(5., 5.));
+        assert_eq!(coordinates.bounds, Rectangle::new(Point::ORIGIN, Size::new(10., 10.)));
+    }
+
+    #[test]
+    fn centroid_of_an_off_center_galaxy() {
+        // Not the center of the bounds when the systems aren't evenly spread
+        let coordinates = GalaxyCoordinates::new(&[
+            Point::new(1000., 2000.),
+            Point::new(1000., 2000.),
+            Point::new(1000., 2000.),
+            Point::new(1400., 2000.),
+        ]);
+
+        assert_eq!(coordinates.centroid, Point::new(1100., 2000.));
+        assert_eq!(coordinates.bounds.center(), Point::new(1200., 2000.));
+        assert_eq!(coordinates.radius, 300.);
+    }
+
+    #[test]
+    fn radial_distance_is_normalized() {
+        let coordinates = GalaxyCoordinates::new(&[
+            Point::new(-100., 0.),
+            Point::new(100., 0.),
+            Point::new(0., 50.),
+            Point::new(0., -50.),
+        ]);
+
+        assert_eq!(coordinates.radius, 100.);
+        assert_eq!(coordinates.radial_distance(Point::ORIGIN), 0.);
+        assert_eq!(coordinates.radial_distance(Point::new(0., 50.)), 0.5);
+        assert_eq!(coordinates.radial_distance(Point::new(-100., 0.)), 1.);
+    }
+
+    #[test]
+    fn single_system_galaxy() {
+        let coordinates = GalaxyCoordinates::new(&[Point::new(7., -3.)]);
+
+        assert_eq!(coordinates.centroid, Point::new(7., -3.));
+        assert_eq!(coordinates.radius, 0.);
+        assert_eq!(coordinates.radial_distance(Point::new(7., -3.)), 0.);
+    }
+
+    #[test]
+    fn empty_galaxy() {
+        let coordinates = GalaxyCoordinates::new(&[]);
+
+        assert_eq!(coordinates.centroid, Point::ORIGIN);
+        assert_eq!(coordinates.radial_distance(Point::new(1., 1.)), 0.);
+    }
+}
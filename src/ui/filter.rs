@@ -0,0 +1,204 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use space_trader_api::models::{self, WaypointModifierSymbol, WaypointTraitSymbol, WaypointType};
+use iced::widget::{button, checkbox, column, pick_list, row, scrollable, text, Column};
+use iced::{Element, Length, Point};
+
+use super::quadtree::bounding_box;
+use super::types::App;
+use crate::store::galaxy::GalaxyStore;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    TypeSelected(WaypointType),
+    ToggleTrait(WaypointTraitSymbol),
+    ToggleModifier(WaypointModifierSymbol),
+    Clear,
+    /// Center the map on a matching system
+    Focus(String),
+}
+
+/// What the waypoints highlighted on the map must be and have
+#[derive(Debug, Default, Clone)]
+pub struct MapFilter {
+    pub waypoint_type: Option<WaypointType>,
+    pub traits: BTreeSet<WaypointTraitSymbol>,
+    pub modifiers: BTreeSet<WaypointModifierSymbol>,
+}
+
+impl MapFilter {
+    pub fn is_active(&self) -> bool {
+        self.waypoint_type.is_some() || !self.traits.is_empty() || !self.modifiers.is_empty()
+    }
+
+    /// Whether a waypoint matches, its traits and modifiers are only known once fetched
+    fn matches(&self, waypoint_type: WaypointType, waypoint: Option<&models::Waypoint>) -> bool {
+        if self.waypoint_type.is_some_and(|expected| expected != waypoint_type) {
+            return false
+        }
+        if self.traits.is_empty() && self.modifiers.is_empty() {
+            return true
+        }
+        let Some(waypoint) = waypoint else {
+            return false
+        };
+
+        let has_traits = self.traits
+            .iter()
+            .all(|symbol| waypoint.traits.iter().any(|waypoint_trait| waypoint_trait.symbol == *symbol));
+        let has_modifiers = self.modifiers
+            .iter()
+            .all(|symbol| waypoint.modifiers.iter().flatten().any(|modifier| modifier.symbol == *symbol));
+
+        has_traits && has_modifiers
+    }
+}
+
+/// The types, traits and modifiers found in the galaxy, to filter by
+#[derive(Debug, Default)]
+pub struct FilterOptions {
+    pub types: Vec<WaypointType>,
+    pub traits: Vec<WaypointTraitSymbol>,
+    pub modifiers: Vec<WaypointModifierSymbol>,
+}
+
+/// Load the fetched waypoints and what they can be filtered by
+pub fn load(app: &mut App) {
+    match GalaxyStore::load() {
+        Ok(galaxy_store) => app.galaxy_store = galaxy_store,
+        Err(err) => app.status = Some(err),
+    }
+
+    let types: BTreeSet<WaypointType> = app.galaxy
+        .iter()
+        .flat_map(|system| &system.waypoints)
+        .map(|waypoint| waypoint.r#type)
+        .collect();
+    let traits: BTreeSet<WaypointTraitSymbol> = app.galaxy_store
+        .waypoints()
+        .flat_map(|waypoint| &waypoint.traits)
+        .map(|waypoint_trait| waypoint_trait.symbol)
+        .collect();
+    let modifiers: BTreeSet<WaypointModifierSymbol> = app.galaxy_store
+        .waypoints()
+        .flat_map(|waypoint| waypoint.modifiers.iter().flatten())
+        .map(|modifier| modifier.symbol)
+        .collect();
+
+    app.filter_options = FilterOptions {
+        types: types.into_iter().collect(),
+        traits: traits.into_iter().collect(),
+        modifiers: modifiers.into_iter().collect(),
+    };
+    apply(app);
+}
+
+pub fn update(app: &mut App, message: Message) {
+    match message {
+        Message::TypeSelected(waypoint_type) => app.map_filter.waypoint_type = Some(waypoint_type),
+        Message::ToggleTrait(symbol) => {
+            if !app.map_filter.traits.remove(&symbol) {
+                app.map_filter.traits.insert(symbol);
+            }
+        }
+        Message::ToggleModifier(symbol) => {
+            if !app.map_filter.modifiers.remove(&symbol) {
+                app.map_filter.modifiers.insert(symbol);
+            }
+        }
+        Message::Clear => app.map_filter = MapFilter::default(),
+        Message::Focus(system_symbol) => {
+            focus(app, &system_symbol);
+            return
+        }
+    }
+
+    apply(app);
+}
+
+/// Find the waypoints matching the filter, and their systems
+fn apply(app: &mut App) {
+    app.filtered_waypoints.clear();
+    app.filtered_systems = BTreeMap::new();
+
+    if app.map_filter.is_active() {
+        for system in &app.galaxy {
+            let matching: Vec<&str> = system.waypoints
+                .iter()
+                .filter(|waypoint| app.map_filter.matches(waypoint.r#type, app.galaxy_store.waypoint(&waypoint.symbol)))
+                .map(|waypoint| waypoint.symbol.as_str())
+                .collect();
+            if matching.is_empty() {
+                continue
+            }
+
+            app.filtered_systems.insert(system.symbol.clone(), matching.len());
+            app.filtered_waypoints.extend(matching.into_iter().map(str::to_string));
+        }
+    }
+
+    app.cache.clear();
+}
+
+/// Zoom on a system and move it to the center of the map
+pub fn focus(app: &mut App, system_symbol: &str) {
+    let Some(system) = app.system_indices.get(system_symbol).map(|&index| &app.galaxy[index]) else {
+        return
+    };
+
+    let area = bounding_box([(Point::new(system.x, system.y), system.radius())].into_iter());
+    app.camera.fit(area);
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let filter = &app.map_filter;
+    let options = &app.filter_options;
+
+    let traits = options.traits
+        .iter()
+        .fold(Column::new().spacing(2), |column, symbol| column.push(
+            checkbox(symbol.to_string(), filter.traits.contains(symbol))
+                .size(14)
+                .text_size(12)
+                .on_toggle(|_| super::Message::Filter(Message::ToggleTrait(*symbol)))
+        ));
+    let modifiers = options.modifiers
+        .iter()
+        .fold(Column::new().spacing(2), |column, symbol| column.push(
+            checkbox(symbol.to_string(), filter.modifiers.contains(symbol))
+                .size(14)
+                .text_size(12)
+                .on_toggle(|_| super::Message::Filter(Message::ToggleModifier(*symbol)))
+        ));
+
+    let matches = app.filtered_systems
+        .iter()
+        .fold(Column::new().spacing(2), |column, (system_symbol, count)| column.push(
+            button(text(format!("{system_symbol}: {count} waypoints")).size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(super::Message::Filter(Message::Focus(system_symbol.clone())))
+        ));
+
+    let summary = match filter.is_active() {
+        true => format!("{} waypoints match in {} systems", app.filtered_waypoints.len(), app.filtered_systems.len()),
+        false => String::from("Pick a type, traits or modifiers"),
+    };
+
+    column![
+        row![
+            pick_list(options.types.as_slice(), filter.waypoint_type, |waypoint_type| {
+                super::Message::Filter(Message::TypeSelected(waypoint_type))
+            })
+                .placeholder("Any type"),
+            button("Clear").on_press(super::Message::Filter(Message::Clear)),
+        ].spacing(10),
+        text(summary).size(14),
+        row![
+            scrollable(column![text("Traits").size(14), traits, text("Modifiers").size(14), modifiers].spacing(5))
+                .width(Length::FillPortion(1)),
+            scrollable(matches).width(Length::FillPortion(1)),
+        ].spacing(10).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
@@ -1,15 +1,3 @@
-use space_trader_api::models::System;
-
-pub fn get_galaxy_radius(galaxy: &Vec<System>) -> f32 {
-    galaxy.iter().fold(0_f32, |state, system| {
-        let x_square = (system.x as f32).powi(2);
-        let y_square = (system.y as f32).powi(2);
-
-        let d = (x_square + y_square).sqrt();
-        state.max(d)
-    }) as f32
-}
-
 pub mod color {
     use space_trader_api::models::SystemType;
 
@@ -21,12 +9,21 @@ pub mod color {
         system: &crate::ui::types::SizedSystem,
         galaxy_color: &colorgrad::Gradient,
     ) -> iced::Color {
-        match system.r#type {
-            SystemType::BlackHole => return iced::Color::BLACK,
-            _ => {}
-        };
+        if system.r#type == SystemType::BlackHole {
+            return iced::Color::BLACK
+        }
+
+        gradient_color(galaxy_color, system.distance)
+    }
+
+    /// The colors of the trade good heatmap, from the lowest to the highest values
+    pub fn get_heatmap_gradient() -> colorgrad::Gradient {
+        colorgrad::turbo()
+    }
 
-        let raw_color = galaxy_color.at(system.distance as f64);
+    /// The color of a gradient at a position from 0 to 1
+    pub fn gradient_color(gradient: &colorgrad::Gradient, position: f32) -> iced::Color {
+        let raw_color = gradient.at(position as f64);
         iced::Color::from_rgb(
             raw_color.r as f32,
             raw_color.g as f32,
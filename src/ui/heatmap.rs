@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use space_trader_api::models::{self, TradeSymbol};
+use iced::widget::{button, canvas, column, pick_list, row, scrollable, text, Canvas, Column};
+use iced::{mouse, Element, Length, Point, Rectangle, Renderer, Size, Theme};
+
+use super::galaxy::color;
+use super::types::App;
+use super::filter;
+use crate::symbols::system_symbol_of;
+use crate::time;
+
+/// The number of best systems listed in the panel
+const BEST_SYSTEMS_LENGTH: usize = 20;
+/// The number of steps of the legend gradient
+const LEGEND_STEPS: usize = 50;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    GoodSelected(TradeSymbol),
+    MetricSelected(Metric),
+    Clear,
+    /// Center the map on a system of the heatmap
+    Focus(String),
+}
+
+/// What the systems are colored by
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// The highest price the good can be sold at
+    #[default]
+    SellPrice,
+    /// The lowest price the good can be bought at
+    BuyPrice,
+    /// The highest supply of the good
+    Supply,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 3] = [Metric::SellPrice, Metric::BuyPrice, Metric::Supply];
+
+    fn value(&self, trade_good: &models::MarketTradeGood) -> i32 {
+        match self {
+            Metric::SellPrice => trade_good.sell_price,
+            Metric::BuyPrice => trade_good.purchase_price,
+            Metric::Supply => trade_good.supply as i32,
+        }
+    }
+
+    /// Whether a value is better than another, the best value of a system is kept
+    fn is_better(&self, value: i32, than: i32) -> bool {
+        match self {
+            Metric::SellPrice | Metric::Supply => value > than,
+            Metric::BuyPrice => value < than,
+        }
+    }
+
+    fn format(&self, value: i32) -> String {
+        match self {
+            Metric::SellPrice | Metric::BuyPrice => value.to_string(),
+            Metric::Supply => SUPPLY_LEVELS.get(value as usize).map_or(String::new(), |supply| supply.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Metric::SellPrice => write!(f, "Best sell price"),
+            Metric::BuyPrice => write!(f, "Best buy price"),
+            Metric::Supply => write!(f, "Supply"),
+        }
+    }
+}
+
+const SUPPLY_LEVELS: [models::SupplyLevel; 5] = [
+    models::SupplyLevel::Scarce,
+    models::SupplyLevel::Limited,
+    models::SupplyLevel::Moderate,
+    models::SupplyLevel::High,
+    models::SupplyLevel::Abundant,
+];
+
+/// The best value of the good in a system
+#[derive(Debug, Clone, Copy)]
+pub struct SystemHeat {
+    pub value: i32,
+    /// From 0 for the worst value of the galaxy to 1 for the best
+    pub position: f32,
+    /// When the prices giving the value were seen
+    pub seen_at: DateTime<Utc>,
+}
+
+/// The systems colored by a trade good instead of their distance to the center
+#[derive(Debug, Default)]
+pub struct Heatmap {
+    pub good: Option<TradeSymbol>,
+    pub metric: Metric,
+    pub systems: HashMap<String, SystemHeat>,
+    /// The worst and best values of the galaxy
+    pub range: Option<(i32, i32)>,
+    /// The goods whose prices were seen
+    pub goods: Vec<TradeSymbol>,
+}
+
+impl Heatmap {
+    pub fn is_active(&self) -> bool {
+        self.good.is_some()
+    }
+}
+
+pub fn update(app: &mut App, message: Message) {
+    match message {
+        Message::GoodSelected(good) => app.heatmap.good = Some(good),
+        Message::MetricSelected(metric) => app.heatmap.metric = metric,
+        Message::Clear => app.heatmap.good = None,
+        Message::Focus(system_symbol) => {
+            filter::focus(app, &system_symbol);
+            return
+        }
+    }
+
+    apply(app);
+}
+
+/// Aggregate the latest prices of the good per system
+pub fn apply(app: &mut App) {
+    let heatmap = &mut app.heatmap;
+    heatmap.systems.clear();
+    heatmap.range = None;
+
+    if let Some(good) = heatmap.good {
+        let metric = heatmap.metric;
+        for (waypoint_symbol, seen_at, trade_good) in app.markets.trade_goods(good) {
+            let value = metric.value(trade_good);
+            let heat = SystemHeat { value, position: 0., seen_at };
+            heatmap.systems
+                .entry(system_symbol_of(waypoint_symbol).to_string())
+                .and_modify(|best| if metric.is_better(value, best.value) { *best = heat })
+                .or_insert(heat);
+        }
+
+        let values = heatmap.systems.values().map(|heat| heat.value);
+        let (min, max) = (values.clone().min(), values.max());
+        if let (Some(min), Some(max)) = (min, max) {
+            let range = match metric.is_better(max, min) {
+                true => (min, max),
+                false => (max, min),
+            };
+            for heat in heatmap.systems.values_mut() {
+                heat.position = match range.0 == range.1 {
+                    true => 1.,
+                    false => (heat.value - range.0) as f32 / (range.1 - range.0) as f32,
+                };
+            }
+            heatmap.range = Some(range);
+        }
+    }
+
+    app.cache.clear();
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let heatmap = &app.heatmap;
+    let controls = row![
+        pick_list(heatmap.goods.as_slice(), heatmap.good, |good| super::Message::Heatmap(Message::GoodSelected(good)))
+            .placeholder("Select a good"),
+        pick_list(&Metric::ALL[..], Some(heatmap.metric), |metric| super::Message::Heatmap(Message::MetricSelected(metric))),
+        button("Clear").on_press(super::Message::Heatmap(Message::Clear)),
+    ].spacing(10);
+
+    let (Some(good), Some((worst, best))) = (heatmap.good, heatmap.range) else {
+        let hint = match heatmap.goods.is_empty() {
+            true => "No market prices seen yet, send ships or probes to the markets",
+            false => "Pick a good to color the systems by its prices",
+        };
+        return column![controls, text(hint).size(14)].spacing(10).into()
+    };
+
+    let mut systems: Vec<(&String, &SystemHeat)> = heatmap.systems.iter().collect();
+    systems.sort_by(|(_, a), (_, b)| b.position.total_cmp(&a.position));
+    let best_systems = systems
+        .into_iter()
+        .take(BEST_SYSTEMS_LENGTH)
+        .fold(Column::new().spacing(2), |column, (system_symbol, heat)| column.push(
+            button(text(format!(
+                "{system_symbol}: {}, seen {}",
+                heatmap.metric.format(heat.value), time::format_age(heat.seen_at)
+            )).size(12))
+                .style(iced::theme::Button::Text)
+                .on_press(super::Message::Heatmap(Message::Focus(system_symbol.clone())))
+        ));
+
+    column![
+        controls,
+        text(format!("{} of {good} in {} systems", heatmap.metric, heatmap.systems.len())).size(14),
+        Canvas::new(Legend).width(Length::Fill).height(16),
+        row![
+            text(heatmap.metric.format(worst)).size(12).width(Length::Fill),
+            text(heatmap.metric.format(best)).size(12),
+        ],
+        text("The ring around a system tells the age of its prices, from green for fresh ones to red for stale ones").size(12),
+        scrollable(best_systems).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+/// The colors of the heatmap, from the worst value on the left to the best on the right
+struct Legend;
+
+impl canvas::Program<super::Message> for Legend {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<<Renderer as canvas::Renderer>::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let gradient = color::get_heatmap_gradient();
+        let step_width = bounds.width / LEGEND_STEPS as f32;
+
+        for step in 0..LEGEND_STEPS {
+            frame.fill_rectangle(
+                Point::new(step as f32 * step_width, 0.),
+                // Overlap the next step to avoid gaps between the rectangles
+                Size::new(step_width + 1., bounds.height),
+                color::gradient_color(&gradient, step as f32 / (LEGEND_STEPS - 1) as f32),
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
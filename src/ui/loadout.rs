@@ -0,0 +1,309 @@
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models::{self, ship_mount::Symbol as MountSymbol, ShipNavStatus};
+use iced::widget::{button, column, progress_bar, row, scrollable, text, Column};
+use iced::{Command, Element, Length};
+
+use super::types::App;
+use crate::automation::retry;
+use crate::store::ledger::{self, LedgerEntry};
+use crate::store::shipyards::ShipyardStore;
+use crate::symbols::{mount_symbol_name, parse_mount_symbol};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Refresh,
+    MountsLoaded(String, Result<Vec<models::ShipMount>, String>),
+    Install(MountSymbol),
+    Remove(MountSymbol),
+    /// A mount was installed or removed, with the ship's new mounts and cargo
+    Modified(String, Result<(Vec<models::ShipMount>, Box<models::ShipCargo>), String>),
+}
+
+/// What a ship's reactor, crew and frame provide, and what its components use
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Budget {
+    pub power_used: i32,
+    pub power_output: i32,
+    pub crew_required: i32,
+    pub crew_capacity: i32,
+    pub slots_used: i32,
+    pub module_slots: i32,
+    pub mounts_used: i32,
+    pub mounting_points: i32,
+}
+
+impl Budget {
+    pub fn of(ship: &models::Ship) -> Budget {
+        let requirements = [&ship.frame.requirements, &ship.reactor.requirements, &ship.engine.requirements]
+            .into_iter()
+            .chain(ship.modules.iter().map(|module| &module.requirements))
+            .chain(ship.mounts.iter().map(|mount| &mount.requirements));
+
+        let mut budget = Budget {
+            power_output: ship.reactor.power_output,
+            crew_capacity: ship.crew.capacity,
+            module_slots: ship.frame.module_slots,
+            mounts_used: ship.mounts.len() as i32,
+            mounting_points: ship.frame.mounting_points,
+            ..Budget::default()
+        };
+        for requirements in requirements {
+            budget.add(requirements);
+        }
+
+        budget
+    }
+
+    /// Add the requirements of a component
+    fn add(&mut self, requirements: &models::ShipRequirements) {
+        self.power_used += requirements.power.unwrap_or_default();
+        self.crew_required += requirements.crew.unwrap_or_default();
+        self.slots_used += requirements.slots.unwrap_or_default();
+    }
+
+    /// The reasons the ship can't run with this budget
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.power_used > self.power_output {
+            problems.push(format!("needs {} power, the reactor outputs {}", self.power_used, self.power_output));
+        }
+        if self.crew_required > self.crew_capacity {
+            problems.push(format!("needs {} crew, the ship has room for {}", self.crew_required, self.crew_capacity));
+        }
+        if self.slots_used > self.module_slots {
+            problems.push(format!("needs {} module slots, the frame has {}", self.slots_used, self.module_slots));
+        }
+        if self.mounts_used > self.mounting_points {
+            problems.push(format!("needs {} mounting points, the frame has {}", self.mounts_used, self.mounting_points));
+        }
+
+        problems
+    }
+}
+
+/// Check the ship can install a mount before calling the API.
+/// The requirements of a mount are only known once seen on a ship.
+pub fn check_install(ship: &models::Ship, symbol: MountSymbol, mount: Option<&models::ShipMount>) -> Result<(), String> {
+    check_docked(ship)?;
+
+    let mount = mount.ok_or(format!(
+        "The requirements of {} are unknown, they're seen on our ships or at the shipyards selling it",
+        mount_symbol_name(symbol)
+    ))?;
+    let mut budget = Budget::of(ship);
+    budget.mounts_used += 1;
+    budget.add(&mount.requirements);
+
+    match budget.problems().as_slice() {
+        [] => Ok(()),
+        problems => Err(format!("The ship {}", problems.join(", "))),
+    }
+}
+
+pub fn check_removal(ship: &models::Ship, symbol: MountSymbol) -> Result<(), String> {
+    check_docked(ship)?;
+
+    match ship.mounts.iter().any(|mount| mount.symbol == symbol) {
+        true => Ok(()),
+        false => Err(format!("{} is not installed", mount_symbol_name(symbol))),
+    }
+}
+
+fn check_docked(ship: &models::Ship) -> Result<(), String> {
+    match ship.nav.status {
+        ShipNavStatus::Docked => Ok(()),
+        _ => Err(format!("{} must be docked at a shipyard to change its mounts", ship.symbol)),
+    }
+}
+
+/// The requirements of a mount, from our ships or the ships sold by the shipyards
+fn known_mount(app: &App, symbol: MountSymbol) -> Option<&models::ShipMount> {
+    app.ships
+        .iter()
+        .flat_map(|ship| &ship.mounts)
+        .chain(app.shipyards.mounts())
+        .find(|mount| mount.symbol == symbol)
+}
+
+/// Get the ship from the API, the ships of the map may be out of date
+async fn fetch_ship(config: &Configuration, ship_symbol: &str) -> Result<models::Ship, String> {
+    retry(|| apis::fleet_api::get_my_ship(config, ship_symbol))
+        .await
+        .map(|response| *response.data)
+        .map_err(|err| format!("Failed to get ship {ship_symbol}: {err}"))
+}
+
+fn selected_ship(app: &App) -> Option<&models::Ship> {
+    app.ships.iter().find(|ship| Some(&ship.symbol) == app.selected_ship.as_ref())
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    let Some(config) = app.config.clone() else {
+        app.status = Some(String::from("A token is required to change loadouts"));
+        return Command::none()
+    };
+    let Some(ship) = selected_ship(app).cloned() else {
+        app.status = Some(String::from("Select a ship to change its loadout"));
+        return Command::none()
+    };
+    let ship_symbol = ship.symbol.clone();
+
+    match message {
+        Message::Refresh => {
+            match ShipyardStore::load() {
+                Ok(shipyards) => app.shipyards = shipyards,
+                Err(err) => app.status = Some(err),
+            }
+            return Command::perform(
+                async move {
+                    let mounts = retry(|| apis::fleet_api::get_mounts(&config, &ship_symbol))
+                        .await
+                        .map(|response| response.data)
+                        .map_err(|err| format!("Failed to get the mounts of {ship_symbol}: {err}"));
+                    (ship_symbol, mounts)
+                },
+                |(ship_symbol, res)| super::Message::Loadout(Message::MountsLoaded(ship_symbol, res))
+            )
+        }
+        Message::Install(symbol) => {
+            let mount = known_mount(app, symbol).cloned();
+            if let Err(err) = check_install(&ship, symbol, mount.as_ref()) {
+                app.status = Some(err);
+                return Command::none()
+            }
+
+            let request = models::InstallMountRequest::new(mount_symbol_name(symbol));
+            return Command::perform(
+                async move {
+                    // The ship may have undocked or changed its mounts since the map loaded it
+                    let ship = fetch_ship(&config, &ship_symbol).await?;
+                    check_install(&ship, symbol, mount.as_ref())?;
+
+                    let response = retry(|| apis::fleet_api::install_mount(&config, &ship_symbol, Some(request.clone())))
+                        .await
+                        .map_err(|err| format!("Failed to install {}: {err}", request.symbol))?;
+
+                    ledger::record(LedgerEntry::from_modification(&response.data.transaction, Some(&response.data.agent)));
+                    Ok((response.data.mounts, response.data.cargo))
+                },
+                move |res| super::Message::Loadout(Message::Modified(ship.symbol, res))
+            )
+        }
+        Message::Remove(symbol) => {
+            if let Err(err) = check_removal(&ship, symbol) {
+                app.status = Some(err);
+                return Command::none()
+            }
+
+            let request = models::RemoveMountRequest::new(mount_symbol_name(symbol));
+            return Command::perform(
+                async move {
+                    let ship = fetch_ship(&config, &ship_symbol).await?;
+                    check_removal(&ship, symbol)?;
+
+                    let response = retry(|| apis::fleet_api::remove_mount(&config, &ship_symbol, Some(request.clone())))
+                        .await
+                        .map_err(|err| format!("Failed to remove {}: {err}", request.symbol))?;
+
+                    ledger::record(LedgerEntry::from_modification(&response.data.transaction, Some(&response.data.agent)));
+                    Ok((response.data.mounts, response.data.cargo))
+                },
+                move |res| super::Message::Loadout(Message::Modified(ship.symbol, res))
+            )
+        }
+        Message::MountsLoaded(ship_symbol, Ok(mounts)) => {
+            if let Some(ship) = app.ships.iter_mut().find(|ship| ship.symbol == ship_symbol) {
+                ship.mounts = mounts;
+            }
+        }
+        Message::Modified(ship_symbol, Ok((mounts, cargo))) => {
+            if let Some(ship) = app.ships.iter_mut().find(|ship| ship.symbol == ship_symbol) {
+                ship.mounts = mounts;
+                ship.cargo = cargo;
+            }
+        }
+        Message::MountsLoaded(_, Err(err)) | Message::Modified(_, Err(err)) => app.status = Some(err),
+    }
+
+    Command::none()
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let Some(ship) = selected_ship(app) else {
+        return text("Select a ship to see its loadout").into()
+    };
+
+    let budget = Budget::of(ship);
+    let gauge = |label: &str, used: i32, available: i32| column![
+        text(format!("{label}: {used}/{available}")).size(14),
+        progress_bar(0.0..=available.max(1) as f32, used as f32).height(8),
+    ];
+
+    // Installing and removing mounts is charged the shipyard's fee
+    let fee = match app.shipyards.get(&ship.nav.waypoint_symbol) {
+        Some(snapshot) => format!("Installing or removing a mount here costs {} credits", snapshot.shipyard.modifications_fee),
+        None => String::from("Dock at a shipyard to see the cost of changing mounts"),
+    };
+
+    let modules = ship.modules
+        .iter()
+        .fold(Column::new().spacing(5), |column, module| column.push(
+            text(format!("{} ({})", module.name, requirements_text(&module.requirements))).size(14)
+        ));
+
+    let mounts = ship.mounts
+        .iter()
+        .fold(Column::new().spacing(5), |column, mount| column.push(row![
+            text(format!("{} ({})", mount.name, requirements_text(&mount.requirements))).size(14).width(Length::Fill),
+            button("Remove").on_press(super::Message::Loadout(Message::Remove(mount.symbol))),
+        ].spacing(10)));
+
+    let installable = ship.cargo.inventory
+        .iter()
+        .filter_map(|item| Some((item, parse_mount_symbol(item.symbol)?)))
+        .fold(Column::new().spacing(5), |column, (item, symbol)| {
+            let requirements = match known_mount(app, symbol) {
+                Some(mount) => requirements_text(&mount.requirements),
+                None => String::from("unknown requirements"),
+            };
+            let check = match check_install(ship, symbol, known_mount(app, symbol)) {
+                Ok(()) => String::new(),
+                Err(err) => format!("\n{err}"),
+            };
+            column.push(row![
+                text(format!("{} x{} ({requirements}){check}", item.name, item.units)).size(14).width(Length::Fill),
+                button("Install").on_press(super::Message::Loadout(Message::Install(symbol))),
+            ].spacing(10))
+        });
+
+    column![
+        row![
+            text(format!("{} - {}", ship.symbol, ship.frame.name)).size(18).width(Length::Fill),
+            button("Refresh").on_press(super::Message::Loadout(Message::Refresh)),
+        ].spacing(10),
+        gauge("Power", budget.power_used, budget.power_output),
+        gauge("Crew", budget.crew_required, budget.crew_capacity),
+        gauge("Module slots", budget.slots_used, budget.module_slots),
+        gauge("Mounting points", budget.mounts_used, budget.mounting_points),
+        text(fee).size(14),
+        scrollable(column![
+            text("Modules").size(16),
+            modules,
+            text("Mounts").size(16),
+            mounts,
+            text("Mounts in cargo").size(16),
+            installable,
+        ].spacing(10)).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn requirements_text(requirements: &models::ShipRequirements) -> String {
+    format!(
+        "power {}, crew {}, slots {}",
+        requirements.power.unwrap_or_default(),
+        requirements.crew.unwrap_or_default(),
+        requirements.slots.unwrap_or_default()
+    )
+}
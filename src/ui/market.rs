@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use space_trader_api::apis;
+use space_trader_api::models;
+use iced::widget::{button, column, row, scrollable, text, Column};
+use iced::{Command, Element, Length};
+
+use super::types::{App, Panel};
+use crate::automation::retry;
+use crate::store::markets::{self, MarketStore};
+use crate::{symbols, time};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Get the market, its prices are only returned when one of our ships is there
+    Refresh,
+    Loaded(Result<Box<models::Market>, String>),
+    /// The markets seen by the ships were read from their file
+    StoreLoaded(Result<Arc<MarketStore>, String>),
+}
+
+/// Read the markets seen by the ships away from the UI thread, the file grows with every visit
+pub fn load() -> Command<super::Message> {
+    Command::perform(
+        async {
+            tokio::task::spawn_blocking(MarketStore::load)
+                .await
+                .map_err(|err| format!("Failed to load the markets: {err}"))?
+                .map(Arc::new)
+        },
+        |res| super::Message::Market(Message::StoreLoaded(res))
+    )
+}
+
+/// Show the last known state of a market
+pub fn open(app: &mut App, waypoint_symbol: String) -> Command<super::Message> {
+    app.selected_market = Some(waypoint_symbol);
+    app.panel = Some(Panel::Market);
+    load()
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    match message {
+        Message::Refresh => {
+            let (Some(config), Some(waypoint_symbol)) = (app.config.clone(), app.selected_market.clone()) else {
+                app.status = Some(String::from("A token is required to get markets"));
+                return Command::none()
+            };
+
+            return Command::perform(
+                async move {
+                    let system_symbol = symbols::system_symbol_of(&waypoint_symbol);
+                    let market = retry(|| apis::systems_api::get_market(&config, system_symbol, &waypoint_symbol))
+                        .await
+                        .map_err(|err| format!("Failed to get the market of {waypoint_symbol}: {err}"))?
+                        .data;
+
+                    markets::record((*market).clone())?;
+                    Ok(market)
+                },
+                |res| super::Message::Market(Message::Loaded(res))
+            )
+        }
+        Message::Loaded(Ok(_)) => return load(),
+        Message::StoreLoaded(Ok(markets)) => {
+            app.markets = markets;
+            app.market_ages = app.markets.priced_at();
+            app.heatmap.goods = app.markets.priced_goods().into_iter().collect();
+            super::heatmap::apply(app);
+        }
+        Message::Loaded(Err(err)) | Message::StoreLoaded(Err(err)) => app.status = Some(err),
+    }
+
+    Command::none()
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let refresh = button("Refresh").on_press(super::Message::Market(Message::Refresh));
+    let Some(waypoint_symbol) = &app.selected_market else {
+        return text("Select a marketplace on the map").into()
+    };
+    let Some(snapshot) = app.markets.latest(waypoint_symbol) else {
+        return column![text(waypoint_symbol).size(18), refresh, text("This market was never seen").size(14)].spacing(10).into()
+    };
+
+    let market = &snapshot.market;
+    let goods = |goods: &[models::TradeGood]| goods
+        .iter()
+        .map(|good| good.symbol.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let prices: Element<_> = match market.trade_goods.as_deref() {
+        Some(trade_goods) => trade_goods
+            .iter()
+            .fold(Column::new().spacing(2), |column, trade_good| column.push(row![
+                text(trade_good.symbol.to_string()).size(12).width(Length::Fill),
+                text(format!(
+                    "buy {} / sell {}, {} supply, volume {}",
+                    trade_good.purchase_price, trade_good.sell_price, trade_good.supply, trade_good.trade_volume
+                )).size(12),
+            ]))
+            .into(),
+        None => text("Send a ship or a probe to the market to see its prices").size(14).into(),
+    };
+
+    column![
+        row![text(waypoint_symbol).size(18).width(Length::Fill), refresh].spacing(10),
+        text(format!("Seen {}", time::format_age(snapshot.timestamp))).size(14),
+        text(format!("Exports: {}", goods(&market.exports))).size(14),
+        text(format!("Imports: {}", goods(&market.imports))).size(14),
+        text(format!("Exchange: {}", goods(&market.exchange))).size(14),
+        scrollable(prices).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
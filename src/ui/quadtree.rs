@@ -0,0 +1,234 @@
+use std::ops::Range;
+
+use iced::{Point, Rectangle};
+
+/// The number of systems under which a node isn't split
+const LEAF_CAPACITY: usize = 8;
+/// Systems sharing the same coordinates can't be split apart
+const MAX_DEPTH: usize = 16;
+
+/// An index of the systems by position, to only visit the visible ones.
+/// The systems are circles: their position and the radius of their waypoints.
+#[derive(Debug, Default)]
+pub struct QuadTree {
+    circles: Vec<(Point, f32)>,
+    nodes: Vec<Node>,
+    /// The indices of the systems, each node covering a contiguous range of them
+    items: Vec<usize>,
+}
+
+#[derive(Debug)]
+struct Node {
+    /// The bounding box of the circles of the node, to skip the nodes out of the area
+    extent: Rectangle,
+    /// The bounding box of the positions of the node, to tell whether its systems are close enough to be clustered
+    positions: Rectangle,
+    range: Range<usize>,
+    children: Vec<usize>,
+}
+
+/// What's visited in an area
+pub enum Visit<'a> {
+    System(usize),
+    /// Systems too close to each other to be told apart at this zoom
+    Cluster(&'a [usize]),
+}
+
+impl QuadTree {
+    pub fn new(circles: Vec<(Point, f32)>) -> QuadTree {
+        let mut tree = QuadTree {
+            items: (0..circles.len()).collect(),
+            circles,
+            nodes: Vec::new(),
+        };
+        if !tree.circles.is_empty() {
+            tree.build(0..tree.items.len(), 0);
+        }
+
+        tree
+    }
+
+    fn build(&mut self, range: Range<usize>, depth: usize) -> usize {
+        let circles = &self.circles;
+        let members = &mut self.items[range.clone()];
+        let extent = bounding_box(members.iter().map(|&item| circles[item]));
+        let positions = bounding_box(members.iter().map(|&item| (circles[item].0, 0.)));
+
+        let index = self.nodes.len();
+        self.nodes.push(Node { extent, positions, range: range.clone(), children: Vec::new() });
+        if range.len() <= LEAF_CAPACITY || depth == MAX_DEPTH {
+            return index
+        }
+
+        // Split the positions in four around their center
+        let center = positions.center();
+        let quadrant = |item: usize| {
+            let position = circles[item].0;
+            (position.x >= center.x) as usize + 2 * (position.y >= center.y) as usize
+        };
+        members.sort_unstable_by_key(|&item| quadrant(item));
+
+        let quadrants: Vec<usize> = members.iter().map(|&item| quadrant(item)).collect();
+        let mut start = 0;
+        let mut children = Vec::new();
+        while start < quadrants.len() {
+            let end = start + quadrants[start..].iter().take_while(|&&q| q == quadrants[start]).count();
+            children.push(self.build(range.start + start..range.start + end, depth + 1));
+            start = end;
+        }
+        self.nodes[index].children = children;
+
+        index
+    }
+
+    /// Visit the systems overlapping an area.
+    /// The nodes smaller than `cluster_size` are visited at once as clusters.
+    pub fn visit<'a>(&'a self, area: Rectangle, cluster_size: f32, mut visitor: impl FnMut(Visit<'a>)) {
+        if !self.nodes.is_empty() {
+            self.visit_node(0, area, cluster_size, &mut visitor);
+        }
+    }
+
+    fn visit_node<'a>(&'a self, index: usize, area: Rectangle, cluster_size: f32, visitor: &mut impl FnMut(Visit<'a>)) {
+        let node = &self.nodes[index];
+        if !overlaps(node.extent, area) {
+            return
+        }
+
+        let items = &self.items[node.range.clone()];
+        // The radius of the systems would keep the largest ones from ever being clustered
+        if items.len() > 1 && node.positions.width.max(node.positions.height) < cluster_size {
+            visitor(Visit::Cluster(items));
+        } else if node.children.is_empty() {
+            items
+                .iter()
+                .filter(|&&item| overlaps(bounding_box([self.circles[item]].into_iter()), area))
+                .for_each(|&item| visitor(Visit::System(item)));
+        } else {
+            for &child in &node.children {
+                self.visit_node(child, area, cluster_size, visitor);
+            }
+        }
+    }
+}
+
+/// The bounding box of circles
+pub fn bounding_box(circles: impl Iterator<Item = (Point, f32)>) -> Rectangle {
+    let (min, max) = circles.fold(
+        (Point::new(f32::MAX, f32::MAX), Point::new(f32::MIN, f32::MIN)),
+        |(min, max), (position, radius)| (
+            Point::new(min.x.min(position.x - radius), min.y.min(position.y - radius)),
+            Point::new(max.x.max(position.x + radius), max.y.max(position.y + radius)),
+        ),
+    );
+
+    Rectangle::new(min, iced::Size::new(max.x - min.x, max.y - min.y))
+}
+
+/// Whether two rectangles overlap, including their edges so empty rectangles can be points
+fn overlaps(a: Rectangle, b: Rectangle) -> bool {
+    a.x <= b.x + b.width && b.x <= a.x + a.width && a.y <= b.y + b.height && b.y <= a.y + a.height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::Size;
+
+    /// The systems and the clusters visited in an area
+    fn visit(tree: &QuadTree, area: Rectangle, cluster_size: f32) -> (Vec<usize>, Vec<Vec<usize>>) {
+        let (mut systems, mut clusters) = (Vec::new(), Vec::new());
+        tree.visit(area, cluster_size, |visit| match visit {
+            Visit::System(item) => systems.push(item),
+            Visit::Cluster(items) => {
+                let mut items = items.to_vec();
+                items.sort_unstable();
+                clusters.push(items);
+            }
+        });
+        systems.sort_unstable();
+        clusters.sort_unstable();
+
+        (systems, clusters)
+    }
+
+    /// A grid of 20 by 20 systems, 10 apart
+    fn grid() -> Vec<(Point, f32)> {
+        (0..400).map(|index| (Point::new((index % 20) as f32 * 10., (index / 20) as f32 * 10.), 1.)).collect()
+    }
+
+    #[test]
+    fn only_the_systems_in_the_area_are_visited() {
+        let circles = grid();
+        let tree = QuadTree::new(circles.clone());
+        let area = Rectangle::new(Point::new(25., 42.), Size::new(60., 35.));
+
+        let expected: Vec<usize> = (0..circles.len())
+            .filter(|&item| overlaps(bounding_box([circles[item]].into_iter()), area))
+            .collect();
+        let (systems, clusters) = visit(&tree, area, 0.);
+
+        assert_eq!(systems, expected);
+        assert_eq!(systems.len(), 6 * 3);
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn empty_tree_visits_nothing() {
+        let tree = QuadTree::new(Vec::new());
+        assert_eq!(visit(&tree, Rectangle::new(Point::ORIGIN, Size::new(100., 100.)), 0.), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn systems_on_the_edges_of_the_area_are_visited() {
+        let tree = QuadTree::new(vec![
+            (Point::new(0., 0.), 0.),
+            (Point::new(10., 5.), 0.),
+            // Reaching the area by its radius only
+            (Point::new(12., 5.), 2.),
+            (Point::new(12.5, 5.), 2.),
+        ]);
+        let (systems, _) = visit(&tree, Rectangle::new(Point::ORIGIN, Size::new(10., 10.)), 0.);
+
+        assert_eq!(systems, [0, 1, 2]);
+    }
+
+    #[test]
+    fn systems_sharing_a_position_are_all_visited() {
+        let tree = QuadTree::new(vec![(Point::new(5., 5.), 1.); 3 * LEAF_CAPACITY]);
+        let (systems, clusters) = visit(&tree, Rectangle::new(Point::ORIGIN, Size::new(10., 10.)), 0.);
+
+        assert_eq!(systems, (0..3 * LEAF_CAPACITY).collect::<Vec<_>>());
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn close_systems_are_clustered() {
+        // Two groups of 20 systems, a thousand apart
+        let circles: Vec<(Point, f32)> = (0..40)
+            .map(|index| {
+                let offset = if index < 20 { 0. } else { 1000. };
+                (Point::new(offset + (index % 5) as f32, offset + (index % 20 / 5) as f32), 3.)
+            })
+            .collect();
+        let tree = QuadTree::new(circles);
+        let area = Rectangle::new(Point::new(-100., -100.), Size::new(1200., 1200.));
+
+        let (systems, clusters) = visit(&tree, area, 50.);
+        assert!(systems.is_empty());
+        assert_eq!(clusters, [(0..20).collect::<Vec<_>>(), (20..40).collect::<Vec<_>>()]);
+
+        let (systems, clusters) = visit(&tree, area, 0.);
+        assert_eq!(systems.len(), 40);
+        assert!(clusters.is_empty());
+    }
+
+    #[test]
+    fn single_system_is_never_a_cluster() {
+        let tree = QuadTree::new(vec![(Point::new(5., 5.), 1.)]);
+        let (systems, clusters) = visit(&tree, Rectangle::new(Point::ORIGIN, Size::new(10., 10.)), 100.);
+
+        assert_eq!(systems, [0]);
+        assert!(clusters.is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+use iced::widget::{button, canvas, column, row, scrollable, text, text_input, Canvas, Column};
+use iced::{mouse, Color, Element, Length, Point, Rectangle, Renderer, Size, Theme};
+
+use super::types::App;
+use crate::report::{parse_date, Breakdown, HourlyProfit, Report};
+use crate::store::ledger::Ledger;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    FromChanged(String),
+    ToChanged(String),
+    Refresh,
+    /// Export the report with the given file extension
+    Export(&'static str),
+}
+
+pub fn update(app: &mut App, message: Message) {
+    match message {
+        Message::FromChanged(from) => app.report_range.0 = from,
+        Message::ToChanged(to) => app.report_range.1 = to,
+        Message::Refresh => match build(app) {
+            Ok(report) => app.report = report,
+            Err(err) => app.status = Some(err),
+        },
+        Message::Export(extension) => {
+            let path = format!("./report.{extension}");
+            app.status = Some(match build(app).and_then(|report| report.export(&path)) {
+                Ok(()) => format!("Exported the report to {path}"),
+                Err(err) => err,
+            });
+        }
+    }
+}
+
+/// Build the report of the range typed in the panel, an empty bound is unlimited
+fn build(app: &App) -> Result<Report, String> {
+    let parse = |date: &str| match date.trim() {
+        "" => Ok(None),
+        date => parse_date(date).map(Some),
+    };
+
+    Ok(Report::new(&Ledger::load(), parse(&app.report_range.0)?, parse(&app.report_range.1)?))
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let report = &app.report;
+
+    let range = row![
+        text_input("From YYYY-MM-DD", &app.report_range.0)
+            .on_input(|from| super::Message::Report(Message::FromChanged(from))),
+        text_input("To YYYY-MM-DD", &app.report_range.1)
+            .on_input(|to| super::Message::Report(Message::ToChanged(to))),
+    ].spacing(10);
+
+    let ships = report.ships
+        .iter()
+        .fold(Column::new().spacing(10), |column, (ship_symbol, breakdown)| column.push(breakdown_view(ship_symbol, breakdown)));
+    let strategies = report.strategies
+        .iter()
+        .fold(Column::new().spacing(10), |column, (strategy, breakdown)| column.push(breakdown_view(strategy, breakdown)));
+
+    column![
+        range,
+        row![
+            button("Refresh").on_press(super::Message::Report(Message::Refresh)),
+            button("Export CSV").on_press(super::Message::Report(Message::Export("csv"))),
+            button("Export JSON").on_press(super::Message::Report(Message::Export("json"))),
+        ].spacing(10),
+        scrollable(column![
+            breakdown_view("Total", &report.total),
+            text(format!("{:.0} credits per hour", report.credits_per_hour)).size(14),
+            Canvas::new(HourlyChart(&report.hourly)).width(Length::Fill).height(100),
+            text("Ships").size(18),
+            ships,
+            text("Strategies").size(18),
+            strategies,
+        ].spacing(10)).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn breakdown_view<'a>(title: &str, breakdown: &Breakdown) -> Element<'a, super::Message> {
+    column![
+        text(format!("{title}: {} net", breakdown.net())).size(16),
+        text(format!("Revenue: {}", breakdown.revenue)).size(14),
+        text(format!(
+            "Costs: {} (fuel {}, repairs {}, ships {}, mounts {}, goods {})",
+            breakdown.costs(), breakdown.fuel, breakdown.repairs, breakdown.ships, breakdown.mounts, breakdown.goods
+        )).size(14),
+    ]
+    .spacing(2)
+    .into()
+}
+
+/// The net profit of every hour as bars, green for profits and red for losses
+struct HourlyChart<'a>(&'a [HourlyProfit]);
+
+impl canvas::Program<super::Message> for HourlyChart<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<<Renderer as canvas::Renderer>::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let max = self.0.iter().map(|hourly| hourly.net.abs()).max().unwrap_or(0).max(1) as f32;
+        let bar_width = bounds.width / self.0.len().max(1) as f32;
+        let middle = bounds.height / 2.;
+
+        for (index, hourly) in self.0.iter().enumerate() {
+            let height = hourly.net.abs() as f32 / max * middle;
+            let (top, color) = match hourly.net >= 0 {
+                true => (middle - height, Color::from_rgb(0.2, 0.8, 0.2)),
+                false => (middle, Color::from_rgb(0.8, 0.2, 0.2)),
+            };
+            frame.fill_rectangle(Point::new(index as f32 * bar_width, top), Size::new(bar_width.max(1.), height), color);
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::Utc;
+use space_trader_api::models;
+use iced::widget::{button, column, row, scrollable, text, Column};
+use iced::{Command, Element, Length};
+
+use super::types::App;
+use crate::automation::scanner;
+use crate::store::scans::{Scan, ScanKind, ScanStore};
+use crate::time;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Scan(ScanKind),
+    /// A ship scanned, with its new cooldown
+    Scanned(String, Result<(Scan, Box<models::Cooldown>), String>),
+}
+
+fn selected_ship(app: &App) -> Option<&models::Ship> {
+    app.ships.iter().find(|ship| Some(&ship.symbol) == app.selected_ship.as_ref())
+}
+
+/// Whether the sensors of the ship are still cooling down
+fn is_cooling_down(ship: &models::Ship) -> bool {
+    ship.cooldown.expiration
+        .as_deref()
+        .and_then(time::parse)
+        .is_some_and(|expiration| expiration > Utc::now())
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    match message {
+        Message::Scan(kind) => {
+            let Some(config) = app.config.clone() else {
+                app.status = Some(String::from("A token is required to scan"));
+                return Command::none()
+            };
+            let Some(mut ship) = selected_ship(app).cloned() else {
+                app.status = Some(String::from("Select a ship to scan with"));
+                return Command::none()
+            };
+            if is_cooling_down(&ship) {
+                app.status = Some(format!("The sensors of {} are cooling down", ship.symbol));
+                return Command::none()
+            }
+
+            let ship_symbol = ship.symbol.clone();
+            return Command::perform(
+                async move {
+                    let scan = scanner::scan(&config, &mut ship, kind).await?;
+                    Ok((scan, ship.cooldown))
+                },
+                move |res| super::Message::Scans(Message::Scanned(ship_symbol, res))
+            )
+        }
+        Message::Scanned(ship_symbol, Ok((_, cooldown))) => {
+            if let Some(ship) = app.ships.iter_mut().find(|ship| ship.symbol == ship_symbol) {
+                ship.cooldown = cooldown;
+            }
+            match ScanStore::load() {
+                Ok(scans) => app.scans = scans,
+                Err(err) => app.status = Some(err),
+            }
+            app.cache.clear();
+        }
+        Message::Scanned(_, Err(err)) => app.status = Some(err),
+    }
+
+    Command::none()
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let actions: Element<_> = match selected_ship(app) {
+        Some(ship) => {
+            let cooling_down = is_cooling_down(ship);
+            let cooldown = match &ship.cooldown.expiration {
+                Some(expiration) if cooling_down => format!("Sensors ready in {}", time::format_countdown(expiration)),
+                _ => String::from("Sensors ready"),
+            };
+
+            let buttons = ScanKind::ALL
+                .into_iter()
+                .fold(row![].spacing(10), |row, kind| row.push(
+                    button(text(format!("Scan {}", kind.to_string().to_lowercase())))
+                        .on_press_maybe((!cooling_down).then_some(super::Message::Scans(Message::Scan(kind))))
+                ));
+
+            column![text(format!("{} - {cooldown}", ship.symbol)).size(14), buttons].spacing(5).into()
+        }
+        None => text("Select a ship to scan with").into(),
+    };
+
+    let history = app.scans
+        .history()
+        .fold(Column::new().spacing(10), |column, scan| {
+            let results = scan.results
+                .describe()
+                .into_iter()
+                .fold(Column::new().spacing(2), |column, line| column.push(text(line).size(12)));
+
+            column.push(column![
+                text(format!(
+                    "{} at {}, {}: {} {}",
+                    scan.ship_symbol,
+                    scan.waypoint_symbol,
+                    time::format_age(scan.timestamp),
+                    scan.results.count(),
+                    scan.results.kind().to_string().to_lowercase()
+                )).size(14),
+                results,
+            ].spacing(5))
+        });
+
+    column![
+        actions,
+        text("Scan history").size(16),
+        scrollable(history).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
@@ -0,0 +1,208 @@
+use space_trader_api::apis::{self, configuration::Configuration};
+use space_trader_api::models;
+use iced::widget::{button, column, pick_list, row, scrollable, text, text_input, Column, Row};
+use iced::{Command, Element, Length};
+
+use super::types::App;
+use crate::automation::{market::get_waypoints_with_trait, retry};
+use crate::store::ledger::{self, LedgerEntry};
+use crate::store::shipyards::{cargo_capacity, ShipyardStore};
+use crate::{symbols, time};
+
+/// The width of a ship type in the comparison
+const COLUMN_WIDTH: f32 = 160.0;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SystemChanged(String),
+    /// Find the shipyards of the typed system, or of the selected ship's system
+    Search,
+    WaypointsLoaded(Result<Vec<String>, String>),
+    Selected(String),
+    Loaded(Result<Box<models::Shipyard>, String>),
+    Purchase(models::ShipType),
+    Purchased(Result<Box<models::Ship>, String>),
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    let Some(config) = app.config.clone() else {
+        app.status = Some(String::from("A token is required to browse shipyards"));
+        return Command::none()
+    };
+
+    match message {
+        Message::SystemChanged(system_symbol) => app.shipyard_system = system_symbol,
+        Message::Search => {
+            let system_symbol = match app.shipyard_system.trim() {
+                "" => match app.ships.iter().find(|ship| Some(&ship.symbol) == app.selected_ship.as_ref()) {
+                    Some(ship) => ship.nav.system_symbol.clone(),
+                    None => {
+                        app.status = Some(String::from("Type a system or select a ship to find shipyards"));
+                        return Command::none()
+                    }
+                },
+                system_symbol => system_symbol.to_string(),
+            };
+
+            return Command::perform(
+                async move {
+                    get_waypoints_with_trait(&config, &system_symbol, models::WaypointTraitSymbol::Shipyard)
+                        .await
+                        .map(|waypoints| waypoints.into_iter().map(|waypoint| waypoint.symbol).collect())
+                },
+                |res| super::Message::Shipyard(Message::WaypointsLoaded(res))
+            )
+        }
+        Message::WaypointsLoaded(Ok(waypoints)) => {
+            if waypoints.is_empty() {
+                app.status = Some(String::from("No shipyard in this system"));
+            }
+            app.shipyard_waypoints = waypoints;
+        }
+        Message::Selected(waypoint_symbol) => {
+            app.selected_shipyard = Some(waypoint_symbol.clone());
+            return load(config, waypoint_symbol)
+        }
+        Message::Loaded(Ok(shipyard)) => {
+            let mut store = match ShipyardStore::load() {
+                Ok(store) => store,
+                Err(err) => {
+                    app.status = Some(err);
+                    return Command::none()
+                }
+            };
+            store.record(*shipyard);
+            if let Err(err) = store.save() {
+                app.status = Some(err);
+            }
+            app.shipyards = store;
+        }
+        Message::Purchase(ship_type) => {
+            let Some(waypoint_symbol) = app.selected_shipyard.clone() else {
+                return Command::none()
+            };
+
+            return Command::perform(
+                async move {
+                    let request = models::PurchaseShipRequest::new(ship_type, waypoint_symbol.clone());
+                    let response = retry(|| apis::fleet_api::purchase_ship(&config, Some(request.clone())))
+                        .await
+                        .map_err(|err| format!("Failed to purchase a {ship_type} at {waypoint_symbol}: {err}"))?;
+
+                    ledger::record(LedgerEntry::from_shipyard(&response.data.transaction, Some(&response.data.agent)));
+                    Ok(response.data.ship)
+                },
+                |res| super::Message::Shipyard(Message::Purchased(res))
+            )
+        }
+        Message::Purchased(Ok(ship)) => {
+            app.status = Some(format!("Purchased {}", ship.symbol));
+            app.ships.push(*ship);
+        }
+        Message::WaypointsLoaded(Err(err))
+        | Message::Loaded(Err(err))
+        | Message::Purchased(Err(err)) => app.status = Some(err),
+    }
+
+    Command::none()
+}
+
+fn load(config: Configuration, waypoint_symbol: String) -> Command<super::Message> {
+    Command::perform(
+        async move {
+            let system_symbol = symbols::system_symbol_of(&waypoint_symbol);
+            retry(|| apis::systems_api::get_shipyard(&config, system_symbol, &waypoint_symbol))
+                .await
+                .map(|response| response.data)
+                .map_err(|err| format!("Failed to get the shipyard of {waypoint_symbol}: {err}"))
+        },
+        |res| super::Message::Shipyard(Message::Loaded(res))
+    )
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let search = row![
+        text_input("System, the selected ship's by default", &app.shipyard_system)
+            .on_input(|system_symbol| super::Message::Shipyard(Message::SystemChanged(system_symbol)))
+            .on_submit(super::Message::Shipyard(Message::Search)),
+        button("Find shipyards").on_press(super::Message::Shipyard(Message::Search)),
+    ].spacing(10);
+
+    let shipyard_list = pick_list(
+        app.shipyard_waypoints.clone(),
+        app.selected_shipyard.clone(),
+        |waypoint_symbol| super::Message::Shipyard(Message::Selected(waypoint_symbol)),
+    ).placeholder("Select a shipyard");
+
+    let snapshot = app.selected_shipyard
+        .as_ref()
+        .and_then(|waypoint_symbol| app.shipyards.get(waypoint_symbol));
+    let Some(snapshot) = snapshot else {
+        return column![search, shipyard_list].spacing(10).into()
+    };
+
+    let shipyard = &snapshot.shipyard;
+    let ships = shipyard.ships.as_deref().unwrap_or_default();
+    let comparison = ships
+        .iter()
+        .fold(Row::new().spacing(10), |row, ship| row.push(ship_view(ship)));
+
+    // The other shipyards may sell the ship types cheaper
+    let prices = shipyard.ship_types
+        .iter()
+        .fold(Column::new().spacing(5), |column, ship_type| {
+            let prices = app.shipyards
+                .prices(ship_type.r#type)
+                .iter()
+                .map(|(waypoint_symbol, price, timestamp)| format!("  {waypoint_symbol}: {price} ({})", time::format_age(*timestamp)))
+                .collect::<Vec<_>>();
+
+            let prices = match prices.is_empty() {
+                true => String::from("  No known price, a ship must visit a shipyard selling it"),
+                false => prices.join("\n"),
+            };
+            column.push(text(format!("{}\n{prices}", ship_type.r#type)).size(14))
+        });
+
+    let seen = match ships.is_empty() {
+        true => String::from("Dock a ship at the shipyard to see its ships and prices"),
+        false => format!("Ships seen {}", time::format_age(snapshot.ships_timestamp())),
+    };
+
+    column![
+        search,
+        shipyard_list,
+        text(format!("{seen}, modifications fee {}", shipyard.modifications_fee)).size(14),
+        scrollable(column![
+            scrollable(comparison).direction(scrollable::Direction::Horizontal(Default::default())),
+            text("Prices across shipyards").size(18),
+            prices,
+        ].spacing(10)).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn ship_view(ship: &models::ShipyardShip) -> Element<'_, super::Message> {
+    let mounts = ship.mounts
+        .iter()
+        .map(|mount| mount.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    column![
+        text(&ship.name).size(16),
+        text(format!("{} credits", ship.purchase_price)).size(14),
+        text(format!("Supply: {:?}", ship.supply)).size(14),
+        text(format!("Cargo: {}", cargo_capacity(ship))).size(14),
+        text(format!("Fuel: {}", ship.frame.fuel_capacity)).size(14),
+        text(format!("Speed: {}", ship.engine.speed)).size(14),
+        text(format!("Crew: {}/{}", ship.crew.required, ship.crew.capacity)).size(14),
+        text(format!("Modules: {} slots", ship.frame.module_slots)).size(14),
+        text(format!("Mounts ({} points): {mounts}", ship.frame.mounting_points)).size(14),
+        button("Purchase").on_press(super::Message::Shipyard(Message::Purchase(ship.r#type))),
+    ]
+    .spacing(3)
+    .width(COLUMN_WIDTH)
+    .into()
+}
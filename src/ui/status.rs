@@ -0,0 +1,88 @@
+use space_trader_api::models;
+use iced::widget::{button, column, row, scrollable, text, Column};
+use iced::{Command, Element, Length};
+
+use super::types::App;
+use crate::{status, time};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Refresh,
+    Loaded(Result<Box<models::GetStatus200Response>, String>),
+}
+
+pub fn load() -> Command<super::Message> {
+    Command::perform(
+        async { status::fetch().await.map(Box::new) },
+        |res| super::Message::Status(Message::Loaded(res))
+    )
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    match message {
+        Message::Refresh => return load(),
+        Message::Loaded(Ok(server_status)) => {
+            if let Some(warning) = status::stale_files_warning(&server_status) {
+                app.status = Some(warning);
+            }
+            app.server_status = Some(server_status);
+        }
+        Message::Loaded(Err(err)) => app.status = Some(err),
+    }
+
+    Command::none()
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let refresh = button("Refresh").on_press(super::Message::Status(Message::Refresh));
+    let Some(server_status) = &app.server_status else {
+        return column![refresh, text("Loading the server status")].spacing(10).into()
+    };
+
+    let leaderboard = |title: &str, entries: Vec<(&str, String)>| entries
+        .into_iter()
+        .enumerate()
+        .fold(Column::new().spacing(2).push(text(title).size(16)), |column, (rank, (agent_symbol, value))| column.push(row![
+            text(format!("{}. {agent_symbol}", rank + 1)).size(14).width(Length::Fill),
+            text(value).size(14),
+        ]));
+
+    let most_credits = leaderboard("Most credits", server_status.leaderboards.most_credits
+        .iter()
+        .map(|entry| (entry.agent_symbol.as_str(), entry.credits.to_string()))
+        .collect());
+    let most_charts = leaderboard("Most submitted charts", server_status.leaderboards.most_submitted_charts
+        .iter()
+        .map(|entry| (entry.agent_symbol.as_str(), entry.chart_count.to_string()))
+        .collect());
+
+    let announcements = server_status.announcements
+        .iter()
+        .fold(Column::new().spacing(5).push(text("Announcements").size(16)), |column, announcement| column.push(column![
+            text(&announcement.title).size(14),
+            text(&announcement.body).size(12),
+        ]));
+    let links = server_status.links
+        .iter()
+        .fold(Column::new().spacing(2), |column, link| column.push(text(format!("{}: {}", link.name, link.url)).size(12)));
+
+    let stats = &server_status.stats;
+    column![
+        row![
+            text(format!("{} ({})", server_status.status, server_status.version)).size(14).width(Length::Fill),
+            refresh,
+        ].spacing(10),
+        text(format!(
+            "Next reset in {} (every {})",
+            time::format_countdown(&server_status.server_resets.next), server_status.server_resets.frequency
+        )).size(18),
+        text(format!("Last reset on {}", server_status.reset_date)).size(14),
+        text(format!(
+            "{} agents, {} ships, {} systems, {} waypoints",
+            stats.agents, stats.ships, stats.systems, stats.waypoints
+        )).size(14),
+        scrollable(column![most_credits, most_charts, announcements, links].spacing(15)).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
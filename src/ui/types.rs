@@ -1,46 +1,161 @@
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use space_trader_api::apis::configuration::Configuration;
 use space_trader_api::models;
-use iced::{widget, Color, Point, Vector};
+use iced::{widget, Color, Point, Rectangle, Size};
+
+use super::{competitors, construction, contracts, filter, heatmap, loadout, market, report, scans, shipyard, status, waypoint};
+use super::camera::Camera;
+use super::coordinates::GalaxyCoordinates;
+use super::filter::{FilterOptions, MapFilter};
+use super::heatmap::Heatmap;
+use super::quadtree::QuadTree;
+use crate::automation::construction::Source;
+use crate::competitors::Ranking;
+use crate::report::Report;
+use crate::store::agents::AgentStore;
+use crate::store::constructions::ConstructionStore;
+use crate::store::galaxy::GalaxyStore;
+use crate::store::markets::MarketStore;
+use crate::store::scans::ScanStore;
+use crate::store::shipyards::ShipyardStore;
 
 #[derive(Default)]
 pub struct App {
     pub cache: widget::canvas::Cache,
+    /// The zoom tier and the part of the galaxy drawn in the cache
+    pub cached_area: Cell<Option<(i32, Rectangle)>>,
     pub galaxy: Vec<SizedSystem>,
-    pub zoom: Zoom,
-    pub nav: Navigation
+    /// Where the systems are, the bounds the camera fits and clamps to
+    pub coordinates: GalaxyCoordinates,
+    /// The systems of the galaxy by position
+    pub system_index: QuadTree,
+    /// The index of the systems in the galaxy, by system symbol
+    pub system_indices: HashMap<String, usize>,
+    pub camera: Camera,
+    pub nav: Navigation,
+    /// The API configuration, only available when a token was given
+    pub config: Option<Configuration>,
+    pub panel: Option<Panel>,
+    pub ships: Vec<models::Ship>,
+    pub selected_ship: Option<String>,
+    pub contracts: Vec<models::Contract>,
+    pub report: Report,
+    /// The first and last days of the report, as typed
+    pub report_range: (String, String),
+    /// The system typed in the shipyard panel
+    pub shipyard_system: String,
+    /// The shipyards of the searched system
+    pub shipyard_waypoints: Vec<String>,
+    pub selected_shipyard: Option<String>,
+    pub shipyards: ShipyardStore,
+    /// When the prices of the markets were last seen, by waypoint symbol
+    pub market_ages: HashMap<String, DateTime<Utc>>,
+    pub scans: ScanStore,
+    pub constructions: ConstructionStore,
+    /// Where to buy the materials of the construction sites, by system and material
+    pub construction_sources: HashMap<(String, models::TradeSymbol), Source>,
+    pub server_status: Option<Box<models::GetStatus200Response>>,
+    pub agents: AgentStore,
+    pub agent_ranking: Ranking,
+    pub selected_agent: Option<String>,
+    /// The number of agents headquartered in each system
+    pub headquarters: HashMap<String, usize>,
+    /// The fetched waypoints with their traits, loaded by the filter panel
+    pub galaxy_store: GalaxyStore,
+    pub map_filter: MapFilter,
+    pub filter_options: FilterOptions,
+    /// The symbols of the waypoints matching the filter
+    pub filtered_waypoints: HashSet<String>,
+    /// The number of matching waypoints of the systems matching the filter
+    pub filtered_systems: BTreeMap<String, usize>,
+    /// The waypoint clicked on the map
+    pub selected_waypoint: Option<String>,
+    /// The jump gate of the selected waypoint, once loaded
+    pub jump_gate: Option<models::JumpGate>,
+    pub selected_market: Option<String>,
+    pub markets: Arc<MarketStore>,
+    /// The trade good the systems are colored by
+    pub heatmap: Heatmap,
+    /// The symbols of the systems to highlight on the map
+    pub highlighted_systems: HashSet<String>,
+    /// The last error or information to display to the user
+    pub status: Option<String>
+}
+
+#[derive(Default)]
+pub struct Flags {
+    pub token: Option<String>
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Message {
     MouseDown,
     MouseUp,
     MouseMoved(Point),
     MouseWheelScrolled(f32),
+    ViewportResized(Size),
+    FitGalaxy,
+    FitSelection,
+    Tick,
+    /// Reload the markets seen by the ships
+    RefreshMarkets,
+    /// Redraw the map, whose colors and labels depend on the time
+    RefreshMap,
+    TogglePanel(Panel),
+    ShipSelected(String),
+    ShipsLoaded(Result<Vec<models::Ship>, String>),
+    Contracts(contracts::Message),
+    Report(report::Message),
+    Shipyard(shipyard::Message),
+    Loadout(loadout::Message),
+    Scans(scans::Message),
+    Construction(construction::Message),
+    Status(status::Message),
+    Competitors(competitors::Message),
+    Filter(filter::Message),
+    Waypoint(waypoint::Message),
+    Market(market::Message),
+    Heatmap(heatmap::Message),
 }
 
-/// The zoom state
-pub struct Zoom {
-    pub scale: f32,
-    pub offset: Vector
-}
-impl Default for Zoom {
-    fn default() -> Self {
-        Zoom {
-            scale: 1.,
-            offset: Vector::ZERO
-        }
-    }
+/// The side panels that can be opened next to the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    Contracts,
+    Report,
+    Shipyard,
+    Loadout,
+    Scans,
+    Construction,
+    Status,
+    Competitors,
+    Filter,
+    Waypoint,
+    Market,
+    Heatmap,
 }
 
-/// The navigation state (the offset of the galaxy)
+/// The mouse state
 #[derive(Default)]
 pub struct Navigation {
-    pub offset_start_position: Option<Point>,
+    /// Where the mouse and the camera were when dragging the map started
+    pub drag_start: Option<(Point, Point)>,
+    /// Where the mouse was pressed, to tell clicks from drags
+    pub pressed_at: Option<Point>,
     pub mouse_current_position: Point,
-    pub offset: Vector
 }
 
+/// The size of the squares of the waypoints, in units of the galaxy
+pub const WAYPOINT_SIZE: f32 = 30.;
+
 /// A waypoint with coordinates and size
 pub struct SizedWaypoint {
+    pub symbol: String,
+    pub r#type: models::WaypointType,
     pub x: f32,
     pub y: f32,
     pub size: f32
@@ -48,17 +163,11 @@ pub struct SizedWaypoint {
 impl SizedWaypoint {
     pub fn from_system_waypoint(waypoint: &models::SystemWaypoint) -> SizedWaypoint {
         SizedWaypoint {
+            symbol: waypoint.symbol.clone(),
+            r#type: waypoint.r#type,
             x: waypoint.x as f32,
             y: waypoint.y as f32,
-            size: 30.
-        }
-    }
-
-    pub fn apply_scale(&self, scale: f32) -> SizedWaypoint {
-        SizedWaypoint {
-            x: self.x * scale,
-            y: self.y * scale,
-            size: self.size * scale
+            size: WAYPOINT_SIZE
         }
     }
 }
@@ -66,22 +175,23 @@ impl SizedWaypoint {
 /// A system with coordinates, size, type, waypoints and color
 /// It's based on the `space_trader_api::models::System` struct
 pub struct SizedSystem {
+    pub symbol: String,
     pub x: f32,
     pub y: f32,
     pub size: f32,
-    /// The distance from the center of the galaxy to the system from 0 to 1
+    /// The distance from the centroid of the galaxy to the system from 0 to 1
     pub distance: f32,
     pub r#type: models::SystemType,
     pub waypoints: Vec<SizedWaypoint>,
     pub color: Color
 }
 impl SizedSystem {
-    pub fn from_system(system: &models::System, galaxy_center: Point, galaxy_radius: f32) -> SizedSystem {
+    pub fn from_system(system: &models::System, coordinates: &GalaxyCoordinates) -> SizedSystem {
         // Convert the waypoints to SizedWaypoint
         let waypoints: Vec<SizedWaypoint> = system
             .waypoints
             .iter()
-            .map(|w| SizedWaypoint::from_system_waypoint(w))
+            .map(SizedWaypoint::from_system_waypoint)
             .collect();
 
         // Calculate the size of the system based on the waypoints
@@ -92,13 +202,10 @@ impl SizedSystem {
             acc.max(x.max(y))
         });
 
-        // Calculate the distance from the center of the galaxy to the system
-        let x_square = (system.x as f32 - galaxy_center.x).powi(2);
-        let y_square = (system.y as f32 - galaxy_center.y).powi(2);
-
-        let distance = (x_square + y_square).sqrt() / galaxy_radius;
+        let distance = coordinates.radial_distance(Point::new(system.x as f32, system.y as f32));
 
         SizedSystem {
+            symbol: system.symbol.clone(),
             x: system.x as f32,
             y: system.y as f32,
             size,
@@ -109,30 +216,8 @@ impl SizedSystem {
         }
     }
 
-    /// Apply a scale to the system and its waypoints
-    pub fn apply_scale(&self, scale: f32) -> SizedSystem {
-        SizedSystem {
-            x: self.x * scale,
-            y: self.y * scale,
-            size: self.size * scale,
-            distance: self.distance,
-            r#type: self.r#type,
-            waypoints: self.
-                waypoints
-                .iter()
-                .map(|w| w.apply_scale(scale))
-                .collect(),
-            color: self.color
-        }
-    }
-
-    /// Offset the position of the system
-    pub fn offset_position(mut self, offset: Vector) -> SizedSystem {
-        self.x += offset.x;
-        self.y += offset.y;
-        // Don't change the waypoint positions
-        // because they are relative to the system position
-
-        self
+    /// The radius of the system including the squares of its waypoints
+    pub fn radius(&self) -> f32 {
+        self.size + self.waypoints.iter().fold(0_f32, |acc, w| acc.max(w.size / 2.))
     }
 }
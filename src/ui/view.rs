@@ -1,11 +1,328 @@
-use iced::{mouse, widget, Color, Point, Renderer, Size, Theme};
-use iced::widget::canvas::{Fill, Path, Style};
+use std::collections::{HashMap, HashSet};
 
+use chrono::{DateTime, TimeDelta, Utc};
+use space_trader_api::models::{self, ShipNavStatus};
+use iced::{event, mouse, widget, Color, Pixels, Point, Renderer, Size, Theme, Transformation};
+use iced::widget::canvas::{Event, Fill, Frame, Path, Stroke, Style, Text};
+
+use super::galaxy::color;
+use super::quadtree::Visit;
 use super::types::*;
+use crate::time;
+
+/// The size of the waypoints on the screen from which they are drawn
+const MIN_DETAIL_SIZE: f32 = 4.;
+/// The systems closer to the centroid of the galaxy are never detailed
+const MIN_DETAIL_DISTANCE: f32 = 0.16;
+/// The size of a waypoint from which the age of its market is shown
+const MIN_LABEL_SIZE: f32 = 12.;
+/// Markets refreshed within this time are considered fresh
+const FRESH_MARKET_AGE: TimeDelta = TimeDelta::minutes(15);
+const STALE_MARKET_AGE: TimeDelta = TimeDelta::hours(2);
+
+const SCANNED_SHIP_COLOR: Color = Color::from_rgb(1., 0.3, 0.3);
+const SCANNED_WAYPOINT_COLOR: Color = Color::from_rgb(0.3, 0.8, 1.);
+const HEADQUARTERS_COLOR: Color = Color::from_rgb(1., 0.6, 0.);
+const FILTER_COLOR: Color = Color::from_rgb(1., 1., 0.);
+/// The systems closer than this on the screen are drawn as one
+const CLUSTER_SIZE: f32 = 2.;
+/// The largest dot drawn for a cluster of systems
+const MAX_CLUSTER_SIZE: f32 = 6.;
+/// The smallest radius of the systems colored by the heatmap
+const HEAT_RADIUS: f32 = 3.;
+/// The opacity of what doesn't match the map filter
+const DIMMED_ALPHA: f32 = 0.15;
+
+fn dim(color: Color, is_dimmed: bool) -> Color {
+    match is_dimmed {
+        true => Color { a: DIMMED_ALPHA, ..color },
+        false => color,
+    }
+}
+
+/// Green for fresh market prices, fading to red for stale ones
+fn staleness_color(priced_at: DateTime<Utc>) -> Color {
+    let age = Utc::now() - priced_at;
+    if age <= FRESH_MARKET_AGE {
+        return Color::from_rgb(0.2, 0.9, 0.2)
+    }
+
+    let staleness = ((age - FRESH_MARKET_AGE).num_seconds() as f32
+        / (STALE_MARKET_AGE - FRESH_MARKET_AGE).num_seconds() as f32).min(1.);
+    Color::from_rgb(0.2 + 0.7 * staleness, 0.9 - 0.7 * staleness, 0.2)
+}
+
+/// How far along its route a ship is, from 0 at the origin to 1 at the destination
+fn route_progress(nav: &models::ShipNav) -> f32 {
+    if nav.status != ShipNavStatus::InTransit {
+        return 1.
+    }
+    let (Some(departure), Some(arrival)) = (time::parse(&nav.route.departure_time), time::parse(&nav.route.arrival)) else {
+        return 1.
+    };
+
+    let total = (arrival - departure).num_seconds().max(1) as f32;
+    ((Utc::now() - departure).num_seconds() as f32 / total).clamp(0., 1.)
+}
+
+/// What's computed once per redraw of the map
+struct DrawContext<'a> {
+    /// The top left corner of the part of the galaxy drawn in the cache
+    origin: Point,
+    /// The scale of the zoom tier
+    scale: f32,
+    sightings: HashMap<&'a str, (DateTime<Utc>, &'a models::ScannedShip)>,
+    scanned_waypoints: HashSet<&'a str>,
+    is_filtered: bool,
+    heatmap_gradient: colorgrad::Gradient,
+}
+
+impl DrawContext<'_> {
+    /// Where a position of the galaxy is in the cached geometry
+    fn project(&self, x: f32, y: f32) -> Point {
+        Point::new((x - self.origin.x) * self.scale, (y - self.origin.y) * self.scale)
+    }
+}
+
+/// Whether the waypoints of a system are drawn at a scale
+fn is_detailed(system: &SizedSystem, scale: f32) -> bool {
+    system.distance > MIN_DETAIL_DISTANCE && WAYPOINT_SIZE * scale >= MIN_DETAIL_SIZE
+}
+
+/// The waypoint drawn under a position of the map, only when zoomed on the systems
+pub fn waypoint_at(app: &App, position: Point) -> Option<String> {
+    let scale = app.camera.tier_scale();
+    let position = app.camera.world_position(position);
+
+    let mut found = None;
+    app.system_index.visit(iced::Rectangle::new(position, Size::ZERO), 0., |visit| {
+        let Visit::System(index) = visit else {
+            return
+        };
+        let system = &app.galaxy[index];
+        if found.is_some() || !is_detailed(system, scale) {
+            return
+        }
+
+        found = system.waypoints
+            .iter()
+            .find(|w| {
+                (position.x - system.x - w.x).abs() <= w.size / 2.
+                    && (position.y - system.y - w.y).abs() <= w.size / 2.
+            })
+            .map(|w| w.symbol.clone());
+    });
+
+    found
+}
+
+impl App {
+    /// Draw the rings around a system, returning the color of the system
+    fn draw_overlays(&self, frame: &mut Frame, system: &SizedSystem, point: Point, context: &DrawContext) -> Color {
+        let size = system.size * context.scale;
+
+        // With a heatmap, the systems are colored by the good and the others dimmed
+        let heat = self.heatmap.systems.get(&system.symbol);
+        let is_dimmed = (context.is_filtered && !self.filtered_systems.contains_key(&system.symbol))
+            || (self.heatmap.is_active() && heat.is_none());
+        let system_color = dim(
+            heat.map_or(system.color, |heat| color::gradient_color(&context.heatmap_gradient, heat.position)),
+            is_dimmed,
+        );
+        if let Some(heat) = heat {
+            frame.fill(&Path::circle(point, size.max(HEAT_RADIUS)), system_color);
+            frame.stroke(
+                &Path::circle(point, size.max(HEAT_RADIUS) + 2.),
+                Stroke::default().with_color(staleness_color(heat.seen_at)).with_width(1.5),
+            );
+        }
+        if context.is_filtered && !is_dimmed {
+            frame.stroke(
+                &Path::circle(point, size.max(6.) + 3.),
+                Stroke::default().with_color(FILTER_COLOR).with_width(2.),
+            );
+        }
+
+        if self.highlighted_systems.contains(&system.symbol) {
+            frame.stroke(
+                &Path::circle(point, size.max(6.)),
+                Stroke::default()
+                    .with_color(Color::from_rgb(0., 1., 0.5))
+                    .with_width(2.),
+            );
+        }
+
+        // The more agents started in a system, the larger its ring
+        if let Some(count) = self.headquarters.get(&system.symbol) {
+            let radius = size.max(4.) + 2. * (*count as f32).sqrt();
+            frame.stroke(
+                &Path::circle(point, radius),
+                Stroke::default().with_color(HEADQUARTERS_COLOR).with_width(1.5),
+            );
+            if *count > 1 {
+                frame.fill_text(Text {
+                    content: count.to_string(),
+                    position: Point::new(point.x + radius, point.y - radius - 12.),
+                    color: HEADQUARTERS_COLOR,
+                    size: Pixels(12.),
+                    ..Text::default()
+                });
+            }
+        }
+
+        system_color
+    }
+
+    /// The systems with a heat, filter, highlight or headquarters overlay, to draw them over the clusters
+    fn marked_systems(&self, context: &DrawContext) -> HashSet<usize> {
+        let filtered = self.filtered_systems.keys().filter(|_| context.is_filtered);
+
+        self.heatmap.systems
+            .keys()
+            .chain(filtered)
+            .chain(&self.highlighted_systems)
+            .chain(self.headquarters.keys())
+            .filter_map(|system_symbol| self.system_indices.get(system_symbol).copied())
+            .collect()
+    }
+
+    /// A single dot for the systems closer than a pixel, the larger the more systems.
+    /// It's dimmed by a filter or a heatmap, the matching systems being drawn over it.
+    fn draw_cluster(&self, frame: &mut Frame, indices: &[usize], context: &DrawContext) {
+        let count = indices.len() as f32;
+        let (x, y, r, g, b) = indices
+            .iter()
+            .map(|&index| &self.galaxy[index])
+            .fold((0., 0., 0., 0., 0.), |(x, y, r, g, b), system| (
+                x + system.x, y + system.y, r + system.color.r, g + system.color.g, b + system.color.b,
+            ));
+
+        let color = dim(
+            Color::from_rgb(r / count, g / count, b / count),
+            context.is_filtered || self.heatmap.is_active(),
+        );
+        let size = (2. + count.sqrt()).min(MAX_CLUSTER_SIZE);
+        frame.fill_rectangle(context.project(x / count, y / count), Size::new(size, size), color)
+    }
+
+    /// Draw the waypoints of a system and the ships seen there
+    fn draw_details(&self, frame: &mut Frame, system: &SizedSystem, point: Point, system_color: Color, context: &DrawContext) {
+        let scale = context.scale;
+        frame.fill(
+            &Path::circle(point, system.size * scale),
+            Fill {
+                style: Style::Solid(system_color),
+                ..Fill::default()
+            },
+        );
+
+        for w in &system.waypoints {
+            let size = w.size * scale;
+            let top_left = Point::new(point.x + w.x * scale - size / 2., point.y + w.y * scale - size / 2.);
+            let market_age = self.market_ages.get(&w.symbol);
+            let waypoint_color = market_age.map_or(Color::WHITE, |priced_at| staleness_color(*priced_at));
+
+            frame.fill_rectangle(
+                top_left,
+                Size::new(size, size),
+                dim(waypoint_color, context.is_filtered && !self.filtered_waypoints.contains(&w.symbol)),
+            );
+
+            // Label the markets with the age of their prices when there's room
+            if let (Some(priced_at), true) = (market_age, size >= MIN_LABEL_SIZE) {
+                frame.fill_text(Text {
+                    content: time::format_age(*priced_at),
+                    position: Point::new(top_left.x, top_left.y + size + 2.),
+                    color: Color::WHITE,
+                    size: Pixels(12.),
+                    ..Text::default()
+                });
+            }
+
+            if context.scanned_waypoints.contains(w.symbol.as_str()) {
+                frame.stroke(
+                    &Path::rectangle(top_left, Size::new(size, size)),
+                    Stroke::default().with_color(SCANNED_WAYPOINT_COLOR).with_width(2.),
+                );
+            }
+        }
+
+        // The other ships seen by our sensors, along their route
+        let waypoint_position = |symbol: &str| system.waypoints
+            .iter()
+            .find(|w| w.symbol == symbol)
+            .map(|w| Point::new(point.x + w.x * scale, point.y + w.y * scale));
+        for (seen_at, ship) in context.sightings.values().filter(|(_, ship)| ship.nav.system_symbol == system.symbol) {
+            let (Some(origin), Some(destination)) = (
+                waypoint_position(&ship.nav.route.origin.symbol),
+                waypoint_position(&ship.nav.route.destination.symbol),
+            ) else {
+                continue
+            };
+
+            let progress = route_progress(&ship.nav);
+            let position = Point::new(
+                origin.x + (destination.x - origin.x) * progress,
+                origin.y + (destination.y - origin.y) * progress,
+            );
+            if ship.nav.status == ShipNavStatus::InTransit {
+                frame.stroke(
+                    &Path::line(origin, destination),
+                    Stroke::default().with_color(Color { a: 0.5, ..SCANNED_SHIP_COLOR }),
+                );
+            }
+            frame.fill(&Path::circle(position, 4.), SCANNED_SHIP_COLOR);
+
+            if system.waypoints.first().is_some_and(|w| w.size * scale >= MIN_LABEL_SIZE) {
+                frame.fill_text(Text {
+                    content: format!(
+                        "{} ({}) {}, seen {}",
+                        ship.registration.name, ship.registration.role, ship.nav.status, time::format_age(*seen_at)
+                    ),
+                    position: Point::new(position.x + 6., position.y - 6.),
+                    color: SCANNED_SHIP_COLOR,
+                    size: Pixels(12.),
+                    ..Text::default()
+                });
+            }
+        }
+    }
+}
 
 impl widget::canvas::Program<Message> for App {
     type State = ();
 
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: Event,
+        bounds: iced::Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let Event::Mouse(mouse_event) = event else {
+            return (event::Status::Ignored, None)
+        };
+
+        let message = match mouse_event {
+            // Only start zooming and panning when the cursor is over the map
+            mouse::Event::WheelScrolled {
+                delta: mouse::ScrollDelta::Pixels { x: _, y },
+            }
+            | mouse::Event::WheelScrolled {
+                delta: mouse::ScrollDelta::Lines { x: _, y },
+            } if cursor.is_over(bounds) => Message::MouseWheelScrolled(y),
+            mouse::Event::ButtonPressed(mouse::Button::Left) if cursor.is_over(bounds) => Message::MouseDown,
+
+            mouse::Event::ButtonReleased(mouse::Button::Left) => Message::MouseUp,
+            mouse::Event::CursorMoved { position } => Message::MouseMoved(
+                Point::new(position.x - bounds.x, position.y - bounds.y)
+            ),
+            _ => return (event::Status::Ignored, None),
+        };
+
+        (event::Status::Captured, Some(message))
+    }
+
     fn draw(
         &self,
         _state: &Self::State,
@@ -14,42 +331,78 @@ impl widget::canvas::Program<Message> for App {
         bounds: iced::Rectangle,
         _cursor: mouse::Cursor,
     ) -> Vec<<Renderer as widget::canvas::Renderer>::Geometry> {
-        let geometry = self.cache.draw(renderer, bounds.size(), |frame| {
+        // The geometry of a zoom tier is drawn around the visible area and moved with the camera,
+        // it's only redrawn when changing tier or moving too far
+        // The size reported by the clip is only applied after the first frame, which would show the default camera
+        let mut camera = self.camera;
+        camera.resize(bounds.size(), self.coordinates.bounds);
+        let visible = camera.visible_area();
+        let tier = camera.tier();
+        let area = match self.cached_area.get() {
+            Some((cached_tier, area)) if cached_tier == tier && contains(area, visible) => area,
+            _ => {
+                let area = iced::Rectangle::new(
+                    Point::new(visible.x - visible.width, visible.y - visible.height),
+                    Size::new(visible.width * 3., visible.height * 3.),
+                );
+                self.cached_area.set(Some((tier, area)));
+                self.cache.clear();
+                area
+            }
+        };
+        let scale = camera.tier_scale();
+
+        let geometry = self.cache.draw(renderer, Size::new(area.width * scale, area.height * scale), |frame| {
             frame.fill_rectangle(Point::ORIGIN, frame.size(), Color::BLACK);
 
-            for system in &self.galaxy {
-                let zoomed_system = system.apply_scale(self.zoom.scale);
-                let point = Point {
-                    x: zoomed_system.x + self.zoom.offset.x + self.nav.offset.x,
-                    y: zoomed_system.y + self.zoom.offset.y + self.nav.offset.y,
-                };
+            let context = DrawContext {
+                origin: area.position(),
+                scale,
+                sightings: self.scans.sightings(),
+                scanned_waypoints: self.scans.scanned_waypoints(),
+                is_filtered: self.map_filter.is_active(),
+                heatmap_gradient: color::get_heatmap_gradient(),
+            };
 
-                if !bounds.expand(zoomed_system.size).contains(point) {
-                    continue;
-                }
+            let mut marked_systems = self.marked_systems(&context);
+            self.system_index.visit(area, CLUSTER_SIZE / scale, |visit| match visit {
+                Visit::System(index) => {
+                    marked_systems.remove(&index);
+                    let system = &self.galaxy[index];
+                    let point = context.project(system.x, system.y);
+                    let system_color = self.draw_overlays(frame, system, point, &context);
 
-                if system.distance > 0.16 && self.zoom.scale > 6.5 {
-                    frame.fill(
-                        &Path::circle(point, zoomed_system.size),
-                        Fill {
-                            style: Style::Solid(zoomed_system.color),
-                            ..Fill::default()
-                        },
-                    );
-
-                    for w in zoomed_system.waypoints {
-                        frame.fill_rectangle(
-                            Point::new(point.x + w.x - w.size / 2., point.y + w.y - w.size / 2.),
-                            Size::new(w.size, w.size),
-                            Color::WHITE,
-                        )
+                    if is_detailed(system, scale) {
+                        self.draw_details(frame, system, point, system_color, &context);
+                    } else {
+                        frame.fill_rectangle(point, Size::new(2., 2.), system_color)
                     }
-                } else {
-                    frame.fill_rectangle(point, Size::new(2., 2.), zoomed_system.color)
                 }
+                Visit::Cluster(indices) => self.draw_cluster(frame, indices, &context),
+            });
+
+            // The marked systems inside the clusters
+            for index in marked_systems {
+                let system = &self.galaxy[index];
+                if !area.contains(Point::new(system.x, system.y)) {
+                    continue
+                }
+
+                let point = context.project(system.x, system.y);
+                let system_color = self.draw_overlays(frame, system, point, &context);
+                frame.fill_rectangle(point, Size::new(2., 2.), system_color)
             }
         });
 
-        vec![geometry]
+        let position = camera.screen_position(area.position());
+        vec![geometry.transform(
+            Transformation::translate(position.x, position.y) * Transformation::scale(camera.scale / scale)
+        )]
     }
 }
+
+fn contains(outer: iced::Rectangle, inner: iced::Rectangle) -> bool {
+    outer.x <= inner.x && outer.y <= inner.y
+        && inner.x + inner.width <= outer.x + outer.width
+        && inner.y + inner.height <= outer.y + outer.height
+}
@@ -0,0 +1,212 @@
+use space_trader_api::apis;
+use space_trader_api::models::{self, WaypointTraitSymbol};
+use iced::widget::{button, column, progress_bar, row, scrollable, text, Column, Row};
+use iced::{Color, Command, Element, Length};
+
+use super::types::{App, Panel};
+use super::{filter, market, shipyard};
+use crate::automation::retry;
+use crate::store::constructions::ConstructionStore;
+use crate::store::galaxy::{self, has_trait, GalaxyStore};
+use crate::{symbols, time};
+
+const WARNING_COLOR: Color = Color::from_rgb(1., 0.6, 0.2);
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// A waypoint was fetched, by waypoint symbol
+    Loaded(String, Result<Box<models::Waypoint>, String>),
+    OpenMarket,
+    OpenShipyard,
+    LoadJumpGate(String),
+    JumpGateLoaded(String, Result<Box<models::JumpGate>, String>),
+    /// Center the map on a system connected by the jump gate
+    Focus(String),
+}
+
+/// Open the panel of a waypoint clicked on the map, fetching it when it's unknown
+pub fn select(app: &mut App, waypoint_symbol: String) -> Command<super::Message> {
+    app.selected_waypoint = Some(waypoint_symbol.clone());
+    app.jump_gate = None;
+    app.panel = Some(Panel::Waypoint);
+    reload_stores(app);
+
+    let Some(config) = app.config.clone() else {
+        return Command::none()
+    };
+    if app.galaxy_store.waypoint(&waypoint_symbol).is_some() {
+        return Command::none()
+    }
+
+    Command::perform(
+        async move {
+            let system_symbol = symbols::system_symbol_of(&waypoint_symbol);
+            let waypoint = retry(|| apis::systems_api::get_waypoint(&config, system_symbol, &waypoint_symbol))
+                .await
+                .map_err(|err| format!("Failed to get waypoint {waypoint_symbol}: {err}"))
+                .and_then(|response| {
+                    galaxy::record(vec![(*response.data).clone()])?;
+                    Ok(response.data)
+                });
+            (waypoint_symbol, waypoint)
+        },
+        |(waypoint_symbol, res)| super::Message::Waypoint(Message::Loaded(waypoint_symbol, res))
+    )
+}
+
+/// Reload the fetched waypoints and construction sites shown in the panel
+fn reload_stores(app: &mut App) {
+    match GalaxyStore::load().and_then(|galaxy_store| Ok((galaxy_store, ConstructionStore::load()?))) {
+        Ok((galaxy_store, constructions)) => {
+            app.galaxy_store = galaxy_store;
+            app.constructions = constructions;
+        }
+        Err(err) => app.status = Some(err),
+    }
+}
+
+pub fn update(app: &mut App, message: Message) -> Command<super::Message> {
+    let Some(waypoint_symbol) = app.selected_waypoint.clone() else {
+        return Command::none()
+    };
+
+    match message {
+        // Another waypoint may have been clicked while fetching this one
+        Message::Loaded(symbol, _) | Message::JumpGateLoaded(symbol, _) if symbol != waypoint_symbol => {}
+        Message::Loaded(_, Ok(_)) => reload_stores(app),
+        Message::OpenMarket => return market::open(app, waypoint_symbol),
+        Message::OpenShipyard => {
+            app.panel = Some(Panel::Shipyard);
+            app.shipyard_system = symbols::system_symbol_of(&waypoint_symbol).to_string();
+            app.shipyard_waypoints = vec![waypoint_symbol.clone()];
+            return shipyard::update(app, shipyard::Message::Selected(waypoint_symbol))
+        }
+        Message::LoadJumpGate(waypoint_symbol) => {
+            let Some(config) = app.config.clone() else {
+                app.status = Some(String::from("A token is required to get jump gates"));
+                return Command::none()
+            };
+
+            return Command::perform(
+                async move {
+                    let system_symbol = symbols::system_symbol_of(&waypoint_symbol);
+                    let jump_gate = retry(|| apis::systems_api::get_jump_gate(&config, system_symbol, &waypoint_symbol))
+                        .await
+                        .map(|response| response.data)
+                        .map_err(|err| format!("Failed to get the jump gate {waypoint_symbol}: {err}"));
+                    (waypoint_symbol, jump_gate)
+                },
+                |(waypoint_symbol, res)| super::Message::Waypoint(Message::JumpGateLoaded(waypoint_symbol, res))
+            )
+        }
+        Message::JumpGateLoaded(_, Ok(jump_gate)) => app.jump_gate = Some(*jump_gate),
+        Message::Focus(system_symbol) => filter::focus(app, &system_symbol),
+        Message::Loaded(_, Err(err)) | Message::JumpGateLoaded(_, Err(err)) => app.status = Some(err),
+    }
+
+    Command::none()
+}
+
+pub fn view(app: &App) -> Element<'_, super::Message> {
+    let Some(waypoint_symbol) = &app.selected_waypoint else {
+        return text("Click a waypoint on the map").into()
+    };
+    let Some(waypoint) = app.galaxy_store.waypoint(waypoint_symbol) else {
+        return column![
+            text(waypoint_symbol).size(18),
+            text("Loading the waypoint, a token is required to fetch it").size(14),
+        ].spacing(10).into()
+    };
+
+    let faction = waypoint.faction
+        .as_ref()
+        .map_or(String::from("No controlling faction"), |faction| format!("Controlled by {}", faction.symbol));
+    let chart = match waypoint.chart.as_deref() {
+        Some(models::Chart { submitted_by: Some(agent), submitted_on, .. }) => format!(
+            "Charted by {agent} {}",
+            submitted_on.as_deref().and_then(time::parse).map_or(String::new(), time::format_age)
+        ),
+        _ if has_trait(waypoint, WaypointTraitSymbol::Uncharted) => String::from("Uncharted"),
+        _ => String::from("Chart unknown"),
+    };
+
+    let modifiers = waypoint.modifiers
+        .iter()
+        .flatten()
+        .fold(Column::new().spacing(5), |column, modifier| column.push(
+            text(format!("Warning, {}: {}", modifier.name, modifier.description)).size(14).style(WARNING_COLOR)
+        ));
+
+    let traits = waypoint.traits
+        .iter()
+        .fold(Column::new().spacing(5), |column, waypoint_trait| column.push(column![
+            text(&waypoint_trait.name).size(14),
+            text(&waypoint_trait.description).size(12),
+        ]));
+
+    let links = [
+        (WaypointTraitSymbol::Marketplace, "Market", Message::OpenMarket),
+        (WaypointTraitSymbol::Shipyard, "Shipyard", Message::OpenShipyard),
+    ]
+        .into_iter()
+        .filter(|(symbol, _, _)| has_trait(waypoint, *symbol))
+        .map(|(_, label, message)| (label, message))
+        .chain((waypoint.r#type == models::WaypointType::JumpGate).then_some(("Jump gate", Message::LoadJumpGate(waypoint.symbol.clone()))))
+        .fold(Row::new().spacing(10), |row, (label, message)| row.push(
+            button(label).on_press(super::Message::Waypoint(message))
+        ));
+
+    column![
+        text(format!("{waypoint_symbol} ({})", waypoint.r#type)).size(18),
+        text(faction).size(14),
+        text(chart).size(14),
+        links,
+        scrollable(column![
+            modifiers,
+            construction_view(app, waypoint),
+            jump_gate_view(app),
+            text("Traits").size(16),
+            traits,
+        ].spacing(10)).height(Length::Fill),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn construction_view<'a>(app: &'a App, waypoint: &models::Waypoint) -> Element<'a, super::Message> {
+    if !waypoint.is_under_construction {
+        return Column::new().into()
+    }
+    let Some(snapshot) = app.constructions.get(&waypoint.symbol) else {
+        return text("Under construction, open the Construction panel to see its progress").size(14).into()
+    };
+
+    snapshot.construction.materials
+        .iter()
+        .fold(
+            Column::new().spacing(5).push(text(format!("Under construction, seen {}", time::format_age(snapshot.timestamp))).size(14)),
+            |column, material| column.push(column![
+                text(format!("{}: {}/{}", material.trade_symbol, material.fulfilled, material.required)).size(12),
+                progress_bar(0.0..=material.required.max(1) as f32, material.fulfilled as f32).height(6),
+            ])
+        )
+        .into()
+}
+
+fn jump_gate_view(app: &App) -> Element<'_, super::Message> {
+    let Some(jump_gate) = &app.jump_gate else {
+        return Column::new().into()
+    };
+
+    jump_gate.connections
+        .iter()
+        .fold(Column::new().spacing(2).push(text("Connections").size(16)), |column, connection| {
+            let system_symbol = symbols::system_symbol_of(connection).to_string();
+            column.push(row![
+                button(text(connection).size(12))
+                    .style(iced::theme::Button::Text)
+                    .on_press(super::Message::Waypoint(Message::Focus(system_symbol))),
+            ])
+        })
+        .into()
+}